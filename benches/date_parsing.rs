@@ -0,0 +1,36 @@
+//! Compares DATE and DATE-TIME parsing through `FromStr`, which takes the fixed-width fast path, with the PEG
+//! grammar it falls back to. Run with `cargo bench --bench date_parsing`.
+
+use std::hint::black_box;
+
+use calpal::types::{ical_type_parser, IcalDate, IcalDateTime};
+use criterion::{criterion_group, criterion_main, Criterion};
+use ical::property::Property;
+
+fn date_times(c: &mut Criterion) {
+    let inputs = ["20240101T090000Z", "20240315T173000", "19970714T173000Z", "20301231T235959"];
+    let mut group = c.benchmark_group("date-time");
+    group.bench_function("fast path", |b| b.iter(|| inputs.map(|input| black_box(input).parse::<IcalDateTime>())));
+    group.bench_function("grammar", |b| b.iter(|| inputs.map(|input| ical_type_parser::date_time(black_box(input)))));
+    group.finish();
+}
+
+fn dates(c: &mut Criterion) {
+    let inputs = ["20240101", "20240315", "19970714", "20301231"];
+    let mut group = c.benchmark_group("date");
+    group.bench_function("fast path", |b| b.iter(|| inputs.map(|input| black_box(input).parse::<IcalDate>())));
+    group.bench_function("grammar", |b| b.iter(|| inputs.map(|input| ical_type_parser::date(black_box(input)))));
+    group.finish();
+}
+
+fn date_time_lists(c: &mut Criterion) {
+    let value = (1..=28).map(|day| format!("202402{day:02}T090000Z")).collect::<Vec<_>>().join(",");
+    let property = Property { name: "EXDATE".to_string(), params: None, value: Some(value.clone()) };
+    let mut group = c.benchmark_group("date-time list");
+    group.bench_function("fast path", |b| b.iter(|| IcalDateTime::list_from(black_box(&property))));
+    group.bench_function("grammar", |b| b.iter(|| ical_type_parser::date_time_list(black_box(&value))));
+    group.finish();
+}
+
+criterion_group!(benches, date_times, dates, date_time_lists);
+criterion_main!(benches);
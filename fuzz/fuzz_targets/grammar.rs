@@ -0,0 +1,27 @@
+//! Throws arbitrary input at the value grammar. Every rule must reject bad input with an error, never panic.
+//! Run with `cargo +nightly fuzz run grammar` from the repository root.
+
+#![no_main]
+
+use calpal::types::{ical_type_parser, IcalRecur};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(value) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(recur) = value.parse::<IcalRecur>() {
+        let _ = recur.validate();
+        let _ = recur.to_ical();
+    }
+    let _ = ical_type_parser::recur(value);
+    let _ = ical_type_parser::duration(value);
+    let _ = ical_type_parser::lenient_duration(value);
+    let _ = ical_type_parser::iso8601_duration(value);
+    let _ = ical_type_parser::date_time(value);
+    let _ = ical_type_parser::lenient_date_time(value);
+    let _ = ical_type_parser::date_time_list(value);
+    let _ = ical_type_parser::period(value);
+    let _ = ical_type_parser::utc_offset(value);
+});
@@ -0,0 +1,154 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use ical::parser::ical::component::IcalAlarm;
+
+use crate::{event::EventTimeRange, types::{get_param, new_property, ICalDuration, IcalDateTime, IcalText}, Error};
+
+pub enum AlarmAction {
+    Audio,
+    Display,
+    Email,
+    Other(String),
+}
+
+impl From<String> for AlarmAction {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "AUDIO" => AlarmAction::Audio,
+            "DISPLAY" => AlarmAction::Display,
+            "EMAIL" => AlarmAction::Email,
+            _ => AlarmAction::Other(value),
+        }
+    }
+}
+
+impl AlarmAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AlarmAction::Audio => "AUDIO",
+            AlarmAction::Display => "DISPLAY",
+            AlarmAction::Email => "EMAIL",
+            AlarmAction::Other(value) => value,
+        }
+    }
+}
+
+pub enum AlarmTriggerRelation {
+    Start,
+    End,
+}
+
+pub enum AlarmTrigger {
+    Relative {
+        duration: ICalDuration,
+        related: AlarmTriggerRelation,
+    },
+    Absolute {
+        date_time: DateTime<Utc>,
+    },
+}
+
+pub struct Alarm {
+    pub action: AlarmAction,
+    pub trigger: AlarmTrigger,
+    pub description: Option<IcalText>,
+}
+
+impl Alarm {
+    /// Resolves the trigger against the parent event's range. Date and floating ranges are treated as UTC.
+    /// `None` when the offset takes the trigger outside the representable range.
+    pub fn trigger_time(&self, range: &EventTimeRange) -> Option<DateTime<Utc>> {
+        match &self.trigger {
+            AlarmTrigger::Absolute { date_time } => Some(*date_time),
+            AlarmTrigger::Relative { duration, related } => {
+                let (start, end) = match range {
+                    EventTimeRange::Date { start, end } => (start.and_time(NaiveTime::MIN).and_utc(), end.and_time(NaiveTime::MIN).and_utc()),
+                    EventTimeRange::DateTime { start, end } => (*start, *end),
+                    EventTimeRange::FloatingDateTime { start, end } => (start.and_utc(), end.and_utc()),
+                };
+
+                match related {
+                    AlarmTriggerRelation::Start => start.checked_add_signed(duration.duration),
+                    AlarmTriggerRelation::End => end.checked_add_signed(duration.duration),
+                }
+            }
+        }
+    }
+}
+
+impl Alarm {
+    pub fn to_component(&self) -> IcalAlarm {
+        let trigger = match &self.trigger {
+            AlarmTrigger::Relative { duration, related: AlarmTriggerRelation::Start } => new_property("TRIGGER", &[], duration.to_ical()),
+            AlarmTrigger::Relative { duration, related: AlarmTriggerRelation::End } => new_property("TRIGGER", &[("RELATED", "END")], duration.to_ical()),
+            AlarmTrigger::Absolute { date_time } => new_property("TRIGGER", &[("VALUE", "DATE-TIME")], IcalDateTime::Utc { date_time: *date_time }.to_ical()),
+        };
+        let mut properties = vec![new_property("ACTION", &[], self.action.as_str().to_string()), trigger];
+        if let Some(description) = &self.description {
+            properties.push(new_property("DESCRIPTION", &[], description.to_ical()));
+        }
+        IcalAlarm { properties }
+    }
+}
+
+impl TryFrom<IcalAlarm> for Alarm {
+    type Error = Error;
+
+    fn try_from(value: IcalAlarm) -> Result<Self, Self::Error> {
+        let mut action = None;
+        let mut trigger = None;
+        let mut description = None;
+
+        for prop in value.properties {
+            match prop.name.as_str() {
+                "ACTION" => action = prop.value.map(AlarmAction::from),
+                "TRIGGER" => {
+                    if get_param(&prop, "VALUE").map(|value| value.as_str()) == Some("DATE-TIME") {
+                        match IcalDateTime::try_from(prop)? {
+                            IcalDateTime::Utc { date_time } => trigger = Some(AlarmTrigger::Absolute { date_time }),
+                            _ => return Err(Error::InvalidAlarm),
+                        }
+                    } else {
+                        let related = match get_param(&prop, "RELATED").map(|value| value.as_str()) {
+                            Some("END") => AlarmTriggerRelation::End,
+                            Some("START") | None => AlarmTriggerRelation::Start,
+                            Some(_) => return Err(Error::InvalidAlarm),
+                        };
+                        trigger = Some(AlarmTrigger::Relative { duration: ICalDuration::try_from(prop)?, related });
+                    }
+                },
+                "DESCRIPTION" => description = Some(IcalText::try_from(prop)?),
+                _ => {}
+            }
+        }
+
+        let action = action.ok_or(Error::InvalidAlarm)?;
+        let trigger = trigger.ok_or(Error::InvalidAlarm)?;
+
+        Ok(Alarm { action, trigger, description })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event::Event, timezone::TimezoneMap};
+
+    use super::*;
+
+    fn event(lines: &str) -> Result<Event, Error> {
+        Event::parse(&format!("DTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\n{lines}"), &TimezoneMap::new())
+    }
+
+    #[test]
+    fn negative_triggers_fire_before() {
+        let parsed = event("BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT15M\r\nEND:VALARM\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER;RELATED=END:-P0DT0H5M\r\nEND:VALARM\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:+PT10M\r\nEND:VALARM\r\n").unwrap();
+        let at = |hour: u32, minute: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, minute, 0).unwrap().and_utc();
+        let times = parsed.alarms.iter().map(|alarm| alarm.trigger_time(&parsed.time)).collect::<Vec<_>>();
+        assert_eq!(times, vec![Some(at(8, 45)), Some(at(9, 55)), Some(at(9, 10))]);
+    }
+
+    #[test]
+    fn out_of_range_triggers_have_no_time() {
+        let parsed = event("BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:P99999999W\r\nEND:VALARM\r\n").unwrap();
+        assert_eq!(parsed.alarms[0].trigger_time(&parsed.time), None);
+    }
+}
@@ -1,5 +1,644 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use ical::{parser::ical::component::{IcalCalendar, IcalTimeZone, IcalTimeZoneTransitionType}, property::Property, IcalParser};
+
+use crate::{event::{knows_timezone, Event, Occurrence, RecurringEventRef}, recur::DEFAULT_MAX_ITERATIONS, timezone::{Timezone, TimezoneMap}, types::{content_line, get_param, is_extension, new_property, IcalExtensions}, Error, MissingTimezonePolicy, ParseOptions};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalendarMethod {
+    Publish,
+    Request,
+    Reply,
+    Add,
+    Cancel,
+    Refresh,
+    Counter,
+    DeclineCounter,
+    Other(String),
+}
+
+impl From<String> for CalendarMethod {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "PUBLISH" => CalendarMethod::Publish,
+            "REQUEST" => CalendarMethod::Request,
+            "REPLY" => CalendarMethod::Reply,
+            "ADD" => CalendarMethod::Add,
+            "CANCEL" => CalendarMethod::Cancel,
+            "REFRESH" => CalendarMethod::Refresh,
+            "COUNTER" => CalendarMethod::Counter,
+            "DECLINECOUNTER" => CalendarMethod::DeclineCounter,
+            _ => CalendarMethod::Other(value),
+        }
+    }
+}
+
+impl CalendarMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CalendarMethod::Publish => "PUBLISH",
+            CalendarMethod::Request => "REQUEST",
+            CalendarMethod::Reply => "REPLY",
+            CalendarMethod::Add => "ADD",
+            CalendarMethod::Cancel => "CANCEL",
+            CalendarMethod::Refresh => "REFRESH",
+            CalendarMethod::Counter => "COUNTER",
+            CalendarMethod::DeclineCounter => "DECLINECOUNTER",
+            CalendarMethod::Other(value) => value,
+        }
+    }
+}
+
+/// Event-level changes between two calendars, see `Calendar::diff`.
+#[derive(Default)]
+pub struct CalendarDiff<'a> {
+    /// Events only in the newer calendar.
+    pub added: Vec<&'a Event>,
+    /// Events only in the older calendar.
+    pub removed: Vec<&'a Event>,
+    /// Matching events whose content changed, as (old, new) pairs.
+    pub modified: Vec<(&'a Event, &'a Event)>,
+}
+
+impl<'a> CalendarDiff<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
 pub struct Calendar {
-    pub year: i32,
-    pub month: i32,
-    pub day: i32,
-}
\ No newline at end of file
+    pub events: Vec<Event>,
+    pub timezones: TimezoneMap,
+    pub warnings: Vec<Error>,
+    /// Events that failed to parse, keyed by their index among the calendar's VEVENT components.
+    pub failures: Vec<(usize, Error)>,
+    /// Identifier of the product that produced the calendar, empty if absent.
+    pub prodid: String,
+    pub version: String,
+    /// The iTIP method, if the calendar is a scheduling message.
+    pub method: Option<CalendarMethod>,
+    /// The zone named by X-WR-TIMEZONE, which some producers intend for floating times. See `apply_default_timezone`.
+    pub default_timezone: Option<String>,
+    pub extensions: IcalExtensions,
+    /// Cap on each recurrence expansion, from `ParseOptions::max_iterations`.
+    pub max_iterations: usize,
+}
+
+impl Calendar {
+    pub fn parse(input: &str, options: &ParseOptions) -> Result<Self, Error> {
+        let component = IcalParser::new(input.as_bytes()).next().ok_or(Error::InvalidCalendar)?.map_err(|_| Error::InvalidCalendar)?;
+        Calendar::from_component(component, options)
+    }
+
+    pub fn from_component(component: IcalCalendar, options: &ParseOptions) -> Result<Self, Error> {
+        let mut warnings = Vec::new();
+
+        let mut prodid = String::new();
+        let mut version = String::new();
+        let mut method = None;
+        let mut extensions = HashMap::new();
+        for prop in component.properties {
+            match prop.name.as_str() {
+                "PRODID" => prodid = prop.value.unwrap_or_default(),
+                "VERSION" => version = prop.value.unwrap_or_default(),
+                "METHOD" => method = prop.value.map(CalendarMethod::from),
+                _ if is_extension(&prop) => extensions.entry(prop.name.clone()).or_insert_with(Vec::new).push(prop),
+                _ => {}
+            }
+        }
+
+        let default_timezone = extensions.get("X-WR-TIMEZONE").and_then(|props| props.first()).and_then(|prop| prop.value.clone());
+
+        if version != "2.0" {
+            options.recover(Error::UnsupportedVersion(version.clone()), &mut warnings)?;
+        }
+
+        let timezones = TimezoneMap::from_components(component.timezones, options.duplicate_timezone)?;
+
+        // Strictly, with no fallback for missing zones, report every undefined TZID up front rather than failing
+        // the events that use them one by one.
+        if options.strict && options.missing_timezone == MissingTimezonePolicy::Reject {
+            let mut missing = component.events.iter()
+                .flat_map(|event| &event.properties)
+                .filter_map(|prop| get_param(prop, "TZID"))
+                .filter(|tzid| !knows_timezone(&timezones, tzid))
+                .cloned()
+                .collect::<Vec<_>>();
+            missing.sort();
+            missing.dedup();
+            if !missing.is_empty() {
+                return Err(Error::MissingTimezones(missing));
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut failures = Vec::new();
+        for (index, event) in component.events.into_iter().enumerate() {
+            match Event::from_component(event, &timezones, options, &mut warnings) {
+                Ok(event) => events.push(event),
+                Err(error) => failures.push((index, error)),
+            }
+        }
+
+        Ok(Calendar { events, timezones, warnings, failures, prodid, version, method, default_timezone, extensions, max_iterations: options.max_iterations })
+    }
+
+    /// The calendar as components, with timezones sorted by TZID. An empty VERSION is written as 2.0.
+    pub fn to_component(&self) -> IcalCalendar {
+        let version = if self.version.is_empty() { "2.0" } else { self.version.as_str() };
+        let mut properties = vec![new_property("PRODID", &[], self.prodid.clone()), new_property("VERSION", &[], version.to_string())];
+        properties.extend(self.method.as_ref().map(|method| new_property("METHOD", &[], method.as_str().to_string())));
+        let mut extensions = self.extensions.iter().collect::<Vec<_>>();
+        extensions.sort_by_key(|(name, _)| name.as_str());
+        properties.extend(extensions.into_iter().flat_map(|(_, props)| props.iter().cloned()));
+
+        let mut timezones = self.timezones.values().collect::<Vec<_>>();
+        timezones.sort_by_key(|timezone| timezone.tzid.as_str());
+
+        IcalCalendar {
+            properties,
+            events: self.events.iter().map(|event| event.to_component(&self.timezones)).collect(),
+            alarms: Vec::new(),
+            todos: Vec::new(),
+            journals: Vec::new(),
+            free_busys: Vec::new(),
+            timezones: timezones.into_iter().cloned().map(IcalTimeZone::from).collect(),
+        }
+    }
+
+    /// Serializes the calendar as iCalendar text, with CRLF line endings and long lines folded.
+    pub fn to_ical(&self) -> String {
+        let calendar = self.to_component();
+        let mut output = String::new();
+        write_component(&mut output, "VCALENDAR", &calendar.properties, |output| {
+            for timezone in &calendar.timezones {
+                write_component(output, "VTIMEZONE", &timezone.properties, |output| {
+                    for transition in &timezone.transitions {
+                        let name = match transition.transition {
+                            IcalTimeZoneTransitionType::STANDARD => "STANDARD",
+                            IcalTimeZoneTransitionType::DAYLIGHT => "DAYLIGHT",
+                        };
+                        write_component(output, name, &transition.properties, |_| {});
+                    }
+                });
+            }
+            for event in &calendar.events {
+                write_component(output, "VEVENT", &event.properties, |output| {
+                    for alarm in &event.alarms {
+                        write_component(output, "VALARM", &alarm.properties, |_| {});
+                    }
+                });
+            }
+        });
+        output
+    }
+
+    /// Reads every event's floating times in `default_timezone`, if there is one. The zone is resolved like any
+    /// TZID and is `MissingTimezone` if unknown. On any error no event is changed.
+    pub fn apply_default_timezone(&mut self) -> Result<(), Error> {
+        let Some(tzid) = &self.default_timezone else {
+            return Ok(());
+        };
+        if !knows_timezone(&self.timezones, tzid) {
+            return Err(Error::MissingTimezone(tzid.clone()));
+        }
+
+        let times = self.events.iter().map(|event| event.times_in(tzid, &self.timezones)).collect::<Result<Vec<_>, _>>()?;
+        for (event, times) in self.events.iter_mut().zip(times) {
+            event.set_times(tzid, times);
+        }
+        Ok(())
+    }
+
+    /// The events grouped by UID, each master with its RECURRENCE-ID overrides, in the order the masters appear.
+    /// An override whose master is missing is grouped on its own, and events without a UID are never grouped.
+    pub fn recurring_events(&self) -> Vec<RecurringEventRef<'_>> {
+        let mut series = Vec::new();
+        let mut by_uid = HashMap::new();
+        for master in self.events.iter().filter(|event| event.recurrence_id.is_none()) {
+            if let Some(uid) = &master.uid {
+                by_uid.entry(uid.as_str()).or_insert(series.len());
+            }
+            series.push(RecurringEventRef::new(master, Vec::new(), &self.timezones).max_iterations(self.max_iterations));
+        }
+
+        for event in self.events.iter().filter(|event| event.recurrence_id.is_some()) {
+            match event.uid.as_deref().and_then(|uid| by_uid.get(uid)) {
+                Some(index) => series[*index].overrides.push(event),
+                None => series.push(RecurringEventRef::new(event, Vec::new(), &self.timezones).max_iterations(self.max_iterations)),
+            }
+        }
+
+        series
+    }
+
+    /// Every occurrence of every event starting within `[start, end]`, sorted by start, expanding each of
+    /// `recurring_events` as in `RecurringEvent::occurrences_between`.
+    pub fn occurrences_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Occurrence<'_>>, Error> {
+        let mut occurrences = Vec::new();
+        for series in self.recurring_events() {
+            occurrences.extend(series.occurrences_between(start, end)?);
+        }
+
+        occurrences.sort_by_key(|occurrence| occurrence.time.start_instant());
+        Ok(occurrences)
+    }
+
+    /// Compares this calendar with a newer version of it. Events are matched like in `merge`, by UID and
+    /// RECURRENCE-ID instant, and count as modified when their timing, summary, description, recurrence rule,
+    /// exceptions, status or sequence differ. Events without a UID cannot be matched and are left out.
+    pub fn diff<'a>(&'a self, other: &'a Calendar) -> CalendarDiff<'a> {
+        let mut diff = CalendarDiff::default();
+
+        for old in self.events.iter().filter(|event| event.uid.is_some()) {
+            match other.events.iter().find(|new| same_instance(old, new)) {
+                Some(new) if changed(old, new) => diff.modified.push((old, new)),
+                Some(_) => {}
+                None => diff.removed.push(old),
+            }
+        }
+
+        diff.added = other.events.iter().filter(|new| new.uid.is_some() && !self.events.iter().any(|old| same_instance(old, new))).collect();
+        diff
+    }
+
+    /// Applies `other` as an update on top of this calendar.
+    ///
+    /// Events are matched by UID and RECURRENCE-ID instant:
+    /// - a match is replaced only when the incoming SEQUENCE is strictly higher, so stale or repeated updates are no-ops;
+    /// - an unmatched event is added, unless it is CANCELLED;
+    /// - a CANCELLED event that wins removes its match instead, and a cancelled master also removes the series' overrides;
+    /// - events without a UID cannot be matched and are always added.
+    ///
+    /// Incoming timezones replace ones with the same TZID and incoming warnings are appended. `other`'s failures are
+    /// dropped since their indices refer to its own components.
+    pub fn merge(&mut self, other: Calendar) {
+        self.timezones.extend(other.timezones);
+        self.warnings.extend(other.warnings);
+
+        for event in other.events {
+            let key = event.key();
+            if key.uid.is_none() {
+                self.events.push(event);
+                continue;
+            }
+
+            let existing = self.events.iter().position(|current| current.key() == key);
+            if let Some(index) = existing {
+                if event.sequence <= self.events[index].sequence {
+                    continue;
+                }
+                self.events.remove(index);
+            }
+
+            if event.is_cancelled() {
+                if key.recurrence_id.is_none() {
+                    self.events.retain(|current| current.uid != key.uid);
+                }
+            } else {
+                self.events.push(event);
+            }
+        }
+    }
+}
+
+fn same_instance(a: &Event, b: &Event) -> bool {
+    a.uid.is_some() && a.key() == b.key()
+}
+
+fn changed(old: &Event, new: &Event) -> bool {
+    old.time != new.time
+        || old.summary != new.summary
+        || old.description != new.description
+        || old.tzid != new.tzid
+        || old.r_rule != new.r_rule
+        || old.rdates != new.rdates
+        || old.exdates != new.exdates
+        || old.status != new.status
+        || old.sequence != new.sequence
+        || old.recurrence_id != new.recurrence_id
+}
+
+fn write_component(output: &mut String, name: &str, properties: &[Property], children: impl FnOnce(&mut String)) {
+    output.push_str(&format!("BEGIN:{name}\r\n"));
+    for property in properties {
+        output.push_str(&content_line(property));
+    }
+    children(output);
+    output.push_str(&format!("END:{name}\r\n"));
+}
+
+/// Assembles a calendar to write out with `Calendar::to_ical`.
+pub struct CalendarBuilder {
+    prodid: String,
+    method: Option<CalendarMethod>,
+    events: Vec<Event>,
+    timezones: TimezoneMap,
+}
+
+impl CalendarBuilder {
+    pub fn new(prodid: impl Into<String>) -> Self {
+        CalendarBuilder { prodid: prodid.into(), method: None, events: Vec::new(), timezones: TimezoneMap::new() }
+    }
+
+    pub fn method(mut self, method: CalendarMethod) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    pub fn event(mut self, event: Event) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    /// Adds a VTIMEZONE, replacing any earlier one with the same TZID.
+    pub fn timezone(mut self, timezone: Timezone) -> Self {
+        self.timezones.insert(timezone.tzid.clone(), timezone);
+        self
+    }
+
+    pub fn build(self) -> Calendar {
+        Calendar {
+            events: self.events,
+            timezones: self.timezones,
+            warnings: Vec::new(),
+            failures: Vec::new(),
+            prodid: self.prodid,
+            version: "2.0".to_string(),
+            method: self.method,
+            default_timezone: None,
+            extensions: HashMap::new(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event::{EventInstant, EventTimeRange}, timezone::tests::{new_york, TEST_VTIMEZONE}};
+
+    use super::*;
+
+    #[test]
+    fn strict_parsing_requires_every_timezone() {
+        let input = format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{TEST_VTIMEZONE}\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;TZID=Test:20240101T090000\r\nDTEND;TZID=Nowhere/B:20240101T100000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;TZID=Nowhere/A:20240101T090000\r\nEXDATE;TZID=Nowhere/B:20240101T090000\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+        assert_eq!(Calendar::parse(&input, &ParseOptions::default()).err(), Some(Error::MissingTimezones(vec!["Nowhere/A".to_string(), "Nowhere/B".to_string()])));
+
+        let lenient = ParseOptions { strict: false, ..ParseOptions::default() };
+        assert_eq!(Calendar::parse(&input, &lenient).unwrap().failures.len(), 2);
+    }
+
+    #[test]
+    fn applies_the_default_timezone_to_floating_times() {
+        let input = format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nX-WR-TIMEZONE:Test\r\n{TEST_VTIMEZONE}\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000\r\nDTEND:20240101T100000\r\nEXDATE:20240108T090000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+        let mut calendar = Calendar::parse(&input, &ParseOptions::default()).unwrap();
+        assert_eq!(calendar.default_timezone.as_deref(), Some("Test"));
+        assert!(matches!(calendar.events[0].time, EventTimeRange::FloatingDateTime { .. }));
+
+        calendar.apply_default_timezone().unwrap();
+        let at = |day: u32, hour: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        assert_eq!(calendar.events[0].time, EventTimeRange::DateTime { start: at(1, 8), end: at(1, 9) });
+        assert_eq!(calendar.events[0].tzid.as_deref(), Some("Test"));
+        assert_eq!(calendar.events[0].exdates, vec![EventInstant::DateTime(at(8, 8))]);
+        assert_eq!(calendar.events[1].time, EventTimeRange::DateTime { start: at(1, 9), end: at(1, 9) });
+        assert_eq!(calendar.events[1].tzid, None);
+
+        calendar.default_timezone = Some("Nowhere/Unknown".to_string());
+        assert_eq!(calendar.apply_default_timezone(), Err(Error::MissingTimezone("Nowhere/Unknown".to_string())));
+    }
+
+    #[test]
+    fn leaves_events_alone_when_the_default_timezone_fails() {
+        let input = format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nX-WR-TIMEZONE:Test\r\n{TEST_VTIMEZONE}\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+        let mut calendar = Calendar::parse(&input, &ParseOptions::default()).unwrap();
+        calendar.events[1].time = EventTimeRange::FloatingDateTime { start: chrono::NaiveDateTime::MIN, end: chrono::NaiveDateTime::MIN };
+
+        assert_eq!(calendar.apply_default_timezone(), Err(Error::InvalidDateTime));
+        assert!(matches!(calendar.events[0].time, EventTimeRange::FloatingDateTime { .. }));
+        assert_eq!(calendar.events[0].tzid, None);
+    }
+
+    #[test]
+    fn builds_a_calendar_that_parses_back() {
+        let timezone = new_york();
+
+        let local = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        let start = timezone.to_utc(local).unwrap();
+        let mut event = Event::new("planning@example.com", EventTimeRange::DateTime { start, end: start + chrono::Duration::hours(1) });
+        event.tzid = Some("America/New_York".to_string());
+        event.local_start = local;
+        event.summary = Some("Planning, Q1".to_string());
+        event.description = Some("Review the roadmap; ".repeat(8) + "then assign owners");
+        event.r_rule = Some("FREQ=WEEKLY;COUNT=4".parse().unwrap());
+        event.exdates = vec![EventInstant::DateTime(timezone.to_utc(local + chrono::Duration::weeks(2)).unwrap())];
+
+        let calendar = CalendarBuilder::new("-//Example//Planner//EN").method(CalendarMethod::Request).timezone(timezone).event(event).build();
+        let output = calendar.to_ical();
+        assert!(output.lines().all(|line| line.len() <= 75));
+        assert!(output.contains("DTSTART;TZID=America/New_York:20240301T090000\r\n"));
+
+        let parsed = Calendar::parse(&output, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.prodid, "-//Example//Planner//EN");
+        assert_eq!(parsed.method, Some(CalendarMethod::Request));
+        let (original, event) = (&calendar.events[0], &parsed.events[0]);
+        assert_eq!(event.uid, original.uid);
+        assert_eq!(event.summary, original.summary);
+        assert_eq!(event.description, original.description);
+        assert_eq!(event.time, original.time);
+        assert_eq!(event.exdates, original.exdates);
+        assert_eq!(event.dtstamp.map(|dtstamp| dtstamp.timestamp()), original.dtstamp.map(|dtstamp| dtstamp.timestamp()));
+        let at = |day: u32, hour: u32| chrono::NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let starts = parsed.occurrences_between(at(1, 0), at(31, 0)).unwrap().iter().map(|occurrence| occurrence.time.start_instant()).collect::<Vec<_>>();
+        assert_eq!(starts, vec![at(1, 14), at(8, 14), at(22, 13)]);
+    }
+
+    #[test]
+    fn derives_a_zoned_start_when_only_the_tzid_is_set() {
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(14, 0, 0).unwrap().and_utc();
+        let mut event = Event::new("standup@example.com", EventTimeRange::DateTime { start, end: start + chrono::Duration::hours(1) });
+        event.tzid = Some("America/New_York".to_string());
+
+        let calendar = CalendarBuilder::new("-//Example//Planner//EN").timezone(new_york()).event(event).build();
+        let output = calendar.to_ical();
+        assert!(output.contains("DTSTART;TZID=America/New_York:20240301T090000\r\n"));
+        assert!(output.contains("DTEND;TZID=America/New_York:20240301T100000\r\n"));
+
+        let parsed = Calendar::parse(&output, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.events[0].time, calendar.events[0].time);
+    }
+
+    #[test]
+    fn keeps_good_events_when_one_fails() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:2024BAD\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        assert_eq!(calendar.events.len(), 1);
+        assert_eq!(calendar.failures.len(), 1);
+        assert_eq!(calendar.failures[0].0, 0);
+    }
+
+    #[test]
+    fn collects_extension_properties() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nX-WR-CALNAME:Work\r\nX-WR-TIMEZONE:Europe/London\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nX-APPLE-TRAVEL-ADVISORY-BEHAVIOR:AUTOMATIC\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        assert_eq!(calendar.extensions["X-WR-CALNAME"][0].value.as_deref(), Some("Work"));
+        assert_eq!(calendar.extensions["X-WR-TIMEZONE"][0].value.as_deref(), Some("Europe/London"));
+        assert!(calendar.events[0].extensions.contains_key("X-APPLE-TRAVEL-ADVISORY-BEHAVIOR"));
+    }
+
+    #[test]
+    fn ignores_non_ascii_property_names() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nAÑB:value\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nÑX-A:value\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        assert!(calendar.extensions.is_empty());
+        assert!(calendar.events[0].extensions.is_empty());
+    }
+
+    #[test]
+    fn parses_method() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nMETHOD:REQUEST\r\nEND:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        assert_eq!(calendar.method, Some(CalendarMethod::Request));
+    }
+
+    #[test]
+    fn expands_every_event_in_a_window() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nSUMMARY:Weekly\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\nRRULE:FREQ=WEEKLY;COUNT=4\r\nRDATE:20240103T120000Z\r\nEXDATE:20240115T090000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nSUMMARY:Moved\r\nRECURRENCE-ID:20240108T090000Z\r\nDTSTART:20240109T090000Z\r\nDTEND:20240109T100000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:single\r\nSUMMARY:Single\r\nDTSTART;VALUE=DATE:20240102\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:orphan\r\nSUMMARY:Orphan\r\nRECURRENCE-ID:20240110T090000Z\r\nDTSTART:20240110T090000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:later\r\nSUMMARY:Later\r\nDTSTART:20240301T090000Z\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+
+        let at = |day: u32, hour: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let occurrences = calendar.occurrences_between(at(1, 0), at(31, 0)).unwrap();
+        let summaries = occurrences.iter().map(|occurrence| (occurrence.event.summary.as_deref().unwrap(), occurrence.time.start_instant())).collect::<Vec<_>>();
+        assert_eq!(summaries, vec![
+            ("Weekly", at(1, 9)),
+            ("Single", at(2, 0)),
+            ("Weekly", at(3, 12)),
+            ("Moved", at(9, 9)),
+            ("Orphan", at(10, 9)),
+            ("Weekly", at(22, 9)),
+        ]);
+    }
+
+    #[test]
+    fn expansion_uses_the_parsed_iteration_cap() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20100101T000000Z\r\nUID:hourly\r\nDTSTART:20100101T000000Z\r\nRRULE:FREQ=HOURLY\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let at = |hour: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        // Periods before the window aren't expanded, so a rule from 2010 stays well within the cap.
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        let starts = calendar.occurrences_between(at(0), at(3)).unwrap().iter().map(|occurrence| occurrence.time.start_instant()).collect::<Vec<_>>();
+        assert_eq!(starts, vec![at(0), at(1), at(2), at(3)]);
+
+        let calendar = Calendar::parse(input, &ParseOptions { max_iterations: 3, ..ParseOptions::default() }).unwrap();
+        assert_eq!(calendar.occurrences_between(at(0), at(3)).err(), Some(Error::IterationLimit));
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn expands_iana_zones_without_vtimezone() {
+        let input = |rule: &str| format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:daily\r\nDTSTART;TZID=America/New_York:20240115T090000\r\nRRULE:{rule}\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n");
+        let at = |day: u32, hour: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let starts = |rule: &str| {
+            let calendar = Calendar::parse(&input(rule), &ParseOptions::default()).unwrap();
+            calendar.occurrences_between(at(1, 0), at(31, 0)).unwrap().iter().map(|occurrence| occurrence.time.start_instant()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(starts("FREQ=DAILY;COUNT=2"), vec![at(15, 14), at(16, 14)]);
+        // UNTIL at the second instance, which is 09:00 in New York.
+        assert_eq!(starts("FREQ=DAILY;UNTIL=20240116T140000Z"), vec![at(15, 14), at(16, 14)]);
+        assert_eq!(starts("FREQ=DAILY;UNTIL=20240116T100000Z"), vec![at(15, 14)]);
+    }
+
+    #[test]
+    fn groups_events_sharing_a_uid() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:orphan\r\nRECURRENCE-ID:20240110T090000Z\r\nDTSTART:20240110T090000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nRECURRENCE-ID:20240108T090000Z\r\nDTSTART:20240109T090000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nDTSTART:20240101T090000Z\r\nRRULE:FREQ=WEEKLY;COUNT=4\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nRECURRENCE-ID:20240115T090000Z\r\nDTSTART:20240116T090000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:single\r\nDTSTART:20240102T090000Z\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let calendar = Calendar::parse(input, &ParseOptions::default()).unwrap();
+        assert_eq!(calendar.events.len(), 5);
+
+        let grouped = calendar.recurring_events();
+        let shape = grouped.iter().map(|series| (series.master.uid.as_deref().unwrap(), series.overrides.len())).collect::<Vec<_>>();
+        assert_eq!(shape, vec![("weekly", 2), ("single", 0), ("orphan", 0)]);
+        assert!(grouped[0].overrides.iter().all(|event| std::ptr::eq(*event, &calendar.events[1]) || std::ptr::eq(*event, &calendar.events[3])));
+    }
+
+    #[test]
+    fn merge_applies_newer_sequences() {
+        let base = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:moved\r\nSEQUENCE:1\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:kept\r\nSEQUENCE:2\r\nDTSTART:20240102T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:cancelled\r\nDTSTART:20240103T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let update = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:moved\r\nSEQUENCE:2\r\nDTSTART:20240101T140000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:kept\r\nSEQUENCE:2\r\nDTSTART:20240102T140000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:cancelled\r\nSEQUENCE:1\r\nSTATUS:CANCELLED\r\nDTSTART:20240103T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:new\r\nDTSTART:20240104T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let mut calendar = Calendar::parse(base, &ParseOptions::default()).unwrap();
+        calendar.merge(Calendar::parse(update, &ParseOptions::default()).unwrap());
+
+        let events = calendar.events.iter().map(|event| (event.uid.as_deref().unwrap(), event.sequence, event.time.start_instant().to_rfc3339())).collect::<Vec<_>>();
+        assert_eq!(events, vec![
+            ("kept", 2, "2024-01-02T09:00:00+00:00".to_string()),
+            ("moved", 2, "2024-01-01T14:00:00+00:00".to_string()),
+            ("new", 0, "2024-01-04T09:00:00+00:00".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn diff_reports_moved_events() {
+        let old = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:moved\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:same\r\nDTSTART:20240102T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:removed\r\nDTSTART:20240103T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let new = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:same\r\nDTSTART:20240102T090000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:moved\r\nDTSTART:20240101T140000Z\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:added\r\nDTSTART:20240104T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let old = Calendar::parse(old, &ParseOptions::default()).unwrap();
+        let new = Calendar::parse(new, &ParseOptions::default()).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added.iter().map(|event| event.uid.as_deref()).collect::<Vec<_>>(), vec![Some("added")]);
+        assert_eq!(diff.removed.iter().map(|event| event.uid.as_deref()).collect::<Vec<_>>(), vec![Some("removed")]);
+        assert_eq!(diff.modified.len(), 1);
+        let (before, after) = diff.modified[0];
+        assert_eq!(before.time.start_instant().to_rfc3339(), "2024-01-01T09:00:00+00:00");
+        assert_eq!(after.time.start_instant().to_rfc3339(), "2024-01-01T14:00:00+00:00");
+
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_retitled_events() {
+        let calendar = |summary: &str| format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:retitled\r\nSUMMARY:{summary}\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+        let old = Calendar::parse(&calendar("Planning"), &ParseOptions::default()).unwrap();
+        let new = Calendar::parse(&calendar("Planning (moved to Room 4)"), &ParseOptions::default()).unwrap();
+
+        let diff = old.diff(&new);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.modified.iter().map(|(before, after)| (before.summary.as_deref(), after.summary.as_deref())).collect::<Vec<_>>(), vec![(Some("Planning"), Some("Planning (moved to Room 4)"))]);
+    }
+
+    #[test]
+    fn requires_version_two_when_strict() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Example//EN\r\nVERSION:1.0\r\nEND:VCALENDAR\r\n";
+        assert_eq!(Calendar::parse(input, &ParseOptions::default()).err(), Some(Error::UnsupportedVersion("1.0".to_string())));
+
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let calendar = Calendar::parse(input, &options).unwrap();
+        assert_eq!(calendar.prodid, "-//Example//EN");
+        assert_eq!(calendar.warnings, vec![Error::UnsupportedVersion("1.0".to_string())]);
+    }
+}
@@ -1,10 +1,16 @@
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use ical::property::Property;
+use std::{collections::HashMap, fmt::{Display, Write}};
 
-use crate::{timezone::TimezoneMap, types::{ICalDuration, IcalDate, IcalDateTime}, Error};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use ical::{parser::ical::component::IcalEvent, property::Property, IcalParser};
 
+use crate::{alarm::Alarm, participant::{Attendee, Organizer}, recur::DEFAULT_MAX_ITERATIONS, timezone::{utc_to_zoned, zoned_to_utc, Timezone, TimezoneMap}, types::{get_param, is_extension, new_property, ICalDuration, IcalAttach, IcalDate, IcalDateTime, IcalExtensions, IcalInteger, IcalRecur, IcalRequestStatus, IcalText}, Error, ICalTypes, MissingTimezonePolicy, ParseOptions};
 
+/// The span of an event, by the value type of DTSTART: a DATE gives `Date`, a UTC or zoned DATE-TIME gives
+/// `DateTime`, and a floating DATE-TIME gives `FloatingDateTime`. The end comes from DTEND, DURATION or the
+/// default duration; DTEND must share DTSTART's value type, and is floating exactly when DTSTART is.
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventTimeRange {
+    /// `end` is exclusive, as in DTEND: a one-day event on January 1st ends on January 2nd.
     Date {
         start: NaiveDate,
         end: NaiveDate
@@ -19,6 +25,157 @@ pub enum EventTimeRange {
     }
 }  
 
+impl EventTimeRange {
+    pub fn start(&self) -> EventInstant {
+        match self {
+            EventTimeRange::Date { start, .. } => EventInstant::Date(*start),
+            EventTimeRange::DateTime { start, .. } => EventInstant::DateTime(*start),
+            EventTimeRange::FloatingDateTime { start, .. } => EventInstant::FloatingDateTime(*start),
+        }
+    }
+
+    /// The part of the range inside the half-open window `[start, end)`, or `None` if they don't overlap. Floating
+    /// times are compared as UTC, and date ranges are clipped to the days the window touches.
+    pub fn clamp(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<EventTimeRange> {
+        match self {
+            EventTimeRange::Date { start: first, end: last } => {
+                let window_end = if end.time() == NaiveTime::MIN { end.date_naive() } else { end.date_naive().succ_opt()? };
+                let (start, end) = intersect(*first, *last, start.date_naive(), window_end)?;
+                Some(EventTimeRange::Date { start, end })
+            },
+            EventTimeRange::DateTime { start: first, end: last } => {
+                let (start, end) = intersect(*first, *last, start, end)?;
+                Some(EventTimeRange::DateTime { start, end })
+            },
+            EventTimeRange::FloatingDateTime { start: first, end: last } => {
+                let (start, end) = intersect(*first, *last, start.naive_utc(), end.naive_utc())?;
+                Some(EventTimeRange::FloatingDateTime { start, end })
+            },
+        }
+    }
+
+    /// The last day a date range covers, one before its exclusive end, for display. A range ending on its start
+    /// day still covers that day. `None` for date-time ranges.
+    pub fn inclusive_end_date(&self) -> Option<NaiveDate> {
+        match self {
+            EventTimeRange::Date { start, end } => Some(end.pred_opt().filter(|last| last >= start).unwrap_or(*start)),
+            EventTimeRange::DateTime { .. } | EventTimeRange::FloatingDateTime { .. } => None,
+        }
+    }
+
+    /// The range as UTC date-times, reading floating times in `default_zone`. Date ranges become midnight-UTC
+    /// date-times, with the end still exclusive, and UTC ranges are returned unchanged.
+    pub fn to_utc(&self, default_zone: &Timezone) -> Result<EventTimeRange, Error> {
+        match self {
+            EventTimeRange::Date { start, end } => Ok(EventTimeRange::DateTime { start: start.and_time(NaiveTime::MIN).and_utc(), end: end.and_time(NaiveTime::MIN).and_utc() }),
+            EventTimeRange::DateTime { .. } => Ok(self.clone()),
+            EventTimeRange::FloatingDateTime { start, end } => Ok(EventTimeRange::DateTime { start: default_zone.to_utc(*start)?, end: default_zone.to_utc(*end)? }),
+        }
+    }
+
+    /// Formats both ends with a chrono format string, as `start - end`. UTC ranges are shown in `zone` and floating
+    /// ranges as they are. Date ranges ignore `fmt` and show their first and last day as `%Y-%m-%d`.
+    pub fn format(&self, zone: &Timezone, fmt: &str) -> Result<String, Error> {
+        let (start, end) = match self {
+            EventTimeRange::Date { start, .. } => {
+                let last = self.inclusive_end_date().unwrap_or(*start);
+                (start.format("%Y-%m-%d").to_string(), last.format("%Y-%m-%d").to_string())
+            },
+            EventTimeRange::DateTime { start, end } => {
+                let zone = zone.as_chrono()?;
+                (checked_format(start.with_timezone(&zone).format(fmt))?, checked_format(end.with_timezone(&zone).format(fmt))?)
+            },
+            EventTimeRange::FloatingDateTime { start, end } => (checked_format(start.format(fmt))?, checked_format(end.format(fmt))?),
+        };
+        Ok(format!("{start} - {end}"))
+    }
+
+    fn end_before_start(&self) -> bool {
+        match self {
+            EventTimeRange::Date { start, end } => end < start,
+            EventTimeRange::DateTime { start, end } => end < start,
+            EventTimeRange::FloatingDateTime { start, end } => end < start,
+        }
+    }
+
+    /// The start projected onto a common timeline, for sorting events of any variant together.
+    pub fn start_instant(&self) -> DateTime<Utc> {
+        self.start().to_utc()
+    }
+
+    /// A range of the same length starting at `instant`, in `instant`'s shape. `None` if the end is out of range.
+    fn starting_at(&self, instant: EventInstant) -> Option<EventTimeRange> {
+        let length = match *self {
+            EventTimeRange::Date { start, end } => end - start,
+            EventTimeRange::DateTime { start, end } => end - start,
+            EventTimeRange::FloatingDateTime { start, end } => end - start,
+        };
+        Some(match instant {
+            EventInstant::Date(start) => EventTimeRange::Date { start, end: start.checked_add_signed(Duration::days(length.num_days()))? },
+            EventInstant::DateTime(start) => EventTimeRange::DateTime { start, end: start.checked_add_signed(length)? },
+            EventInstant::FloatingDateTime(start) => EventTimeRange::FloatingDateTime { start, end: start.checked_add_signed(length)? },
+        })
+    }
+
+    /// Moves both ends by `by`, truncated to whole days for dates. `None` if either end is out of range.
+    fn shifted(&self, by: Duration) -> Option<EventTimeRange> {
+        Some(match *self {
+            EventTimeRange::Date { start, end } => {
+                let days = Duration::days(by.num_days());
+                EventTimeRange::Date { start: start.checked_add_signed(days)?, end: end.checked_add_signed(days)? }
+            },
+            EventTimeRange::DateTime { start, end } => EventTimeRange::DateTime { start: start.checked_add_signed(by)?, end: end.checked_add_signed(by)? },
+            EventTimeRange::FloatingDateTime { start, end } => EventTimeRange::FloatingDateTime { start: start.checked_add_signed(by)?, end: end.checked_add_signed(by)? },
+        })
+    }
+}
+
+/// Renders a chrono format, which fails rather than panicking on a bad format string.
+fn checked_format(formatted: impl Display) -> Result<String, Error> {
+    let mut output = String::new();
+    write!(output, "{formatted}").map_err(|_| Error::InvalidFormat)?;
+    Ok(output)
+}
+
+/// Zero-length ranges overlap the window when they fall inside it.
+fn intersect<T: Ord + Copy>(start: T, end: T, window_start: T, window_end: T) -> Option<(T, T)> {
+    let overlaps = start < window_end && (end > window_start || (start == end && start >= window_start));
+    overlaps.then(|| (start.max(window_start), end.min(window_end)))
+}
+
+/// A single point on an event's timeline, in the same shapes as `EventTimeRange`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventInstant {
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    FloatingDateTime(NaiveDateTime),
+}
+
+impl EventInstant {
+    /// Projects the instant onto UTC, treating dates as midnight and floating times as UTC.
+    pub fn to_utc(self) -> DateTime<Utc> {
+        match self {
+            EventInstant::Date(date) => date.and_time(NaiveTime::MIN).and_utc(),
+            EventInstant::DateTime(date_time) => date_time,
+            EventInstant::FloatingDateTime(date_time) => date_time.and_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RecurrenceRange {
+    #[default]
+    ThisInstance,
+    ThisAndFuture,
+}
+
+/// Identifies which occurrence of the master event an override replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecurrenceId {
+    pub instant: EventInstant,
+    pub range: RecurrenceRange,
+}
+
 enum TimeValue {
     Date(IcalDate),
     DateTime(IcalDateTime),
@@ -28,10 +185,160 @@ impl TryFrom<Property> for TimeValue {
     type Error = Error;
 
     fn try_from(value: Property) -> Result<Self, Self::Error> {
+        if is_date_value(&value) {
+            return IcalDate::try_from(value).map(TimeValue::Date);
+        }
         IcalDate::try_from(value.clone()).map(TimeValue::Date).or_else(|_| IcalDateTime::try_from(value).map(TimeValue::DateTime))
     }
 }
 
+fn is_date_value(property: &Property) -> bool {
+    get_param(property, "VALUE").is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+}
+
+fn parse_time_value(property: Property, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<TimeValue, Error> {
+    match TimeValue::try_from(property.clone()) {
+        Ok(value) => Ok(value),
+        Err(error) => match parse_lenient_time_value(property) {
+            Ok(value) => {
+                options.recover(error, warnings)?;
+                Ok(value)
+            },
+            Err(_) => Err(error),
+        }
+    }
+}
+
+/// A `VALUE=DATE` value carrying a time is cut down to its date, anything else goes through the lenient date-time parser.
+fn parse_lenient_time_value(property: Property) -> Result<TimeValue, Error> {
+    if is_date_value(&property) {
+        let date = property.value.as_deref().and_then(|value| value.split_once('T')).ok_or(Error::TypeDecode(ICalTypes::Date))?.0;
+        return date.parse().map(TimeValue::Date);
+    }
+    IcalDateTime::try_from_lenient(property).map(TimeValue::DateTime)
+}
+
+/// CREATED, LAST-MODIFIED and DTSTAMP, which must be UTC date-times. Leniently, any other value is skipped.
+fn parse_timestamp(property: Property, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<Option<DateTime<Utc>>, Error> {
+    match IcalDateTime::try_from(property) {
+        Ok(IcalDateTime::Utc { date_time }) => Ok(Some(date_time)),
+        Ok(_) => options.recover(Error::InvalidDateTime, warnings).map(|()| None),
+        Err(error) => options.recover(error, warnings).map(|()| None),
+    }
+}
+
+/// Whether a TZID can be resolved, either by a VTIMEZONE or, with the `chrono-tz` feature, as an IANA zone name.
+pub(crate) fn knows_timezone(timezone_map: &TimezoneMap, tzid: &str) -> bool {
+    #[cfg(feature = "chrono-tz")]
+    if tzid.parse::<chrono_tz::Tz>().is_ok() {
+        return true;
+    }
+    timezone_map.contains_key(tzid)
+}
+
+fn resolve_missing_timezone(value: TimeValue, timezone_map: &TimezoneMap, options: &ParseOptions, warnings: &mut Vec<Error>) -> TimeValue {
+    match value {
+        TimeValue::DateTime(IcalDateTime::TimeZone { date_time, tzid }) if !knows_timezone(timezone_map, &tzid) => {
+            let resolved = match options.missing_timezone {
+                MissingTimezonePolicy::Reject => return TimeValue::DateTime(IcalDateTime::TimeZone { date_time, tzid }),
+                MissingTimezonePolicy::Utc => IcalDateTime::Utc { date_time: date_time.and_utc() },
+                MissingTimezonePolicy::Floating => IcalDateTime::Floating { date_time },
+            };
+            warnings.push(Error::MissingTimezone(tzid));
+            TimeValue::DateTime(resolved)
+        },
+        value => value,
+    }
+}
+
+impl TimeValue {
+    fn wall_clock(&self) -> NaiveDateTime {
+        match self {
+            TimeValue::Date(date) => date.date.and_time(NaiveTime::MIN),
+            TimeValue::DateTime(IcalDateTime::Utc { date_time }) => date_time.naive_utc(),
+            TimeValue::DateTime(IcalDateTime::Floating { date_time } | IcalDateTime::TimeZone { date_time, .. }) => *date_time,
+        }
+    }
+
+    fn to_instant(&self, timezone_map: &TimezoneMap) -> Result<EventInstant, Error> {
+        match self {
+            TimeValue::Date(date) => Ok(EventInstant::Date(date.date)),
+            TimeValue::DateTime(IcalDateTime::Utc { date_time }) => Ok(EventInstant::DateTime(*date_time)),
+            TimeValue::DateTime(IcalDateTime::Floating { date_time }) => Ok(EventInstant::FloatingDateTime(*date_time)),
+            TimeValue::DateTime(IcalDateTime::TimeZone { date_time, tzid }) => Ok(EventInstant::DateTime(zoned_to_utc(*date_time, tzid, timezone_map)?)),
+        }
+    }
+}
+
+fn parse_instants(property: Property, timezone_map: &TimezoneMap, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<Vec<EventInstant>, Error> {
+    let values = match IcalDateTime::list_from(&property) {
+        Ok(date_times) => date_times.into_iter().map(TimeValue::DateTime).collect(),
+        // DATE lists, and values only the lenient parser accepts, are handled one at a time.
+        Err(_) => property.value.clone().unwrap_or_default().split(',').map(|value| {
+            let property = Property { name: property.name.clone(), params: property.params.clone(), value: Some(value.to_string()) };
+            parse_time_value(property, options, warnings)
+        }).collect::<Result<Vec<_>, _>>()?,
+    };
+
+    values.into_iter().map(|value| resolve_missing_timezone(value, timezone_map, options, warnings).to_instant(timezone_map)).collect()
+}
+
+impl RecurrenceId {
+    fn from_property(property: Property, timezone_map: &TimezoneMap, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<Self, Error> {
+        let range = match get_param(&property, "RANGE") {
+            None => RecurrenceRange::ThisInstance,
+            Some(range) if range.eq_ignore_ascii_case("THISANDFUTURE") => RecurrenceRange::ThisAndFuture,
+            Some(_) => {
+                options.recover(Error::InvalidRecurrenceId, warnings)?;
+                RecurrenceRange::ThisInstance
+            },
+        };
+        let value = parse_time_value(property, options, warnings)?;
+        let value = resolve_missing_timezone(value, timezone_map, options, warnings);
+
+        Ok(RecurrenceId { instant: value.to_instant(timezone_map)?, range })
+    }
+}
+
+fn parse_duration(property: Property, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<ICalDuration, Error> {
+    match ICalDuration::try_from(property.clone()) {
+        Ok(duration) => Ok(duration),
+        Err(error) => match ICalDuration::try_from_lenient(property) {
+            Ok(duration) => {
+                options.recover(error, warnings)?;
+                Ok(duration)
+            },
+            Err(_) => Err(error),
+        }
+    }
+}
+
+/// Adds a duration to a date. A date covers whole days, so any partial day extends the result through that day.
+fn add_days(date: NaiveDate, duration: chrono::Duration) -> Result<NaiveDate, Error> {
+    let days = duration.num_days() + if duration > chrono::Duration::days(duration.num_days()) { 1 } else { 0 };
+    chrono::Duration::try_days(days).and_then(|days| date.checked_add_signed(days)).ok_or(Error::InvalidDate)
+}
+
+/// Resolves DATE-TIME endpoints, which must both be UTC, both floating or both zoned.
+fn date_time_range(start: IcalDateTime, end: IcalDateTime, timezone_map: &TimezoneMap) -> Result<EventTimeRange, Error> {
+    match (start, end) {
+        (IcalDateTime::Utc { date_time: start }, IcalDateTime::Utc { date_time: end }) => Ok(EventTimeRange::DateTime { start, end }),
+        (IcalDateTime::Floating { date_time: start }, IcalDateTime::Floating { date_time: end }) => Ok(EventTimeRange::FloatingDateTime { start, end }),
+        // A floating endpoint has no instant to compare against the other one.
+        (IcalDateTime::Floating { .. }, _) | (_, IcalDateTime::Floating { .. }) => Err(Error::InvalidDateTime),
+        // UTC and zoned endpoints may be mixed; each is resolved in its own zone.
+        (start, end) => Ok(EventTimeRange::DateTime { start: anchored_to_utc(start, timezone_map)?, end: anchored_to_utc(end, timezone_map)? }),
+    }
+}
+
+fn anchored_to_utc(date_time: IcalDateTime, timezone_map: &TimezoneMap) -> Result<DateTime<Utc>, Error> {
+    match date_time {
+        IcalDateTime::Utc { date_time } => Ok(date_time),
+        IcalDateTime::TimeZone { date_time, tzid } => zoned_to_utc(date_time, &tzid, timezone_map),
+        IcalDateTime::Floating { .. } => Err(Error::InvalidDateTime),
+    }
+}
+
 struct RawTiming {
     start: Property,
     end: Option<Property>,
@@ -39,58 +346,1254 @@ struct RawTiming {
 }
 
 impl RawTiming {
-    pub fn get_time_range(&self, timezone_map: &TimezoneMap) -> Result<EventTimeRange, Error> {
-        let start = TimeValue::try_from(self.start.clone())?;
-        let end = self.end.clone().map(|end| TimeValue::try_from(end)).transpose()?;
-        let duration = self.duration.clone().map(|duration| ICalDuration::try_from(duration)).transpose()?;
+    pub fn get_time_range(&self, timezone_map: &TimezoneMap, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<EventTimeRange, Error> {
+        let start = parse_time_value(self.start.clone(), options, warnings)?;
+        let start = resolve_missing_timezone(start, timezone_map, options, warnings);
+        let end = self.end.clone().map(|end| parse_time_value(end, options, warnings)).transpose()?;
+        let end = end.map(|end| resolve_missing_timezone(end, timezone_map, options, warnings));
+        let duration = self.duration.clone().map(|duration| parse_duration(duration, options, warnings)).transpose()?;
+
+        // RFC 5545 requires DTSTART and DTEND to share a value type. Leniently, a DATE start is read as midnight in
+        // the caller's default zone.
+        let start = match (start, &end) {
+            ( TimeValue::Date(start), Some(TimeValue::DateTime(end)) ) => {
+                if options.strict {
+                    return Err(Error::InvalidTimeRange);
+                }
+                warnings.push(Error::PromotedDateStart);
+                let date_time = start.date.and_time(NaiveTime::MIN);
+                TimeValue::DateTime(match (end, &options.default_timezone) {
+                    (IcalDateTime::Floating { .. }, _) => IcalDateTime::Floating { date_time },
+                    (_, Some(tzid)) => IcalDateTime::TimeZone { date_time, tzid: tzid.clone() },
+                    (_, None) => IcalDateTime::Utc { date_time: date_time.and_utc() },
+                })
+            },
+            ( start, _ ) => start,
+        };
 
-        match (start, end, duration) {
+        let range = match (start, end, duration) {
             ( TimeValue::Date(start), None, None ) => {
-                let start = start.date;
-                Ok(EventTimeRange::Date { start, end: start + chrono::Duration::days(1) })
+                Ok(EventTimeRange::Date { start: start.date, end: add_days(start.date, options.default_duration.date)? })
             },
             ( TimeValue::Date(start), Some(TimeValue::Date(end)), None ) => {
                 Ok(EventTimeRange::Date { start: start.date, end: end.date })
             },
-            ( TimeValue::DateTime(start), None, None ) => {
-                match start {
-                    IcalDateTime::Utc { date_time } => Ok(EventTimeRange::DateTime { start: date_time, end: date_time + chrono::Duration::days(1) }),
-                    IcalDateTime::Floating { date_time } => Ok(EventTimeRange::FloatingDateTime { start: date_time, end: date_time + chrono::Duration::days(1) }),
-                    IcalDateTime::TimeZone { date_time, tzid } => {
-                        let timezone = timezone_map.get(&tzid).ok_or(Error::InvalidTimezone)?;
-                        let next_day = date_time.date().succ_opt().ok_or(Error::InvalidDate)?;
-                        let end_date_time = NaiveDateTime::new(next_day, NaiveTime::MIN) - chrono::Duration::seconds(1);
-                        Ok(EventTimeRange::DateTime { start: timezone.to_utc(date_time)?, end: timezone.to_utc(end_date_time)? })
-                    }
-                }
-            },
-            ( TimeValue::DateTime(start), Some(TimeValue::DateTime(end)), None ) => {
-                match (start, end) {
-                    (IcalDateTime::Utc { date_time: start }, IcalDateTime::Utc { date_time: end }) => Ok(EventTimeRange::DateTime { start, end }),
-                    (IcalDateTime::Floating { date_time: start }, IcalDateTime::Floating { date_time: end }) => Ok(EventTimeRange::FloatingDateTime { start, end }),
-                    (IcalDateTime::TimeZone { date_time: start, tzid: start_tzid }, IcalDateTime::TimeZone { date_time: end, tzid: end_tzid }) => {
-                        let start_timezone = timezone_map.get(&start_tzid).ok_or(Error::InvalidTimezone)?;
-                        let end_timezone = timezone_map.get(&end_tzid).ok_or(Error::InvalidTimezone)?;
-                        Ok(EventTimeRange::DateTime { start: start_timezone.to_utc(start)?, end: end_timezone.to_utc(end)? })
-                    },
-                    _ => Err(Error::InvalidDateTime)
-                }
+            ( TimeValue::Date(start), None, Some(duration) ) => {
+                Ok(EventTimeRange::Date { start: start.date, end: add_days(start.date, duration.duration)? })
             },
-            ( TimeValue::DateTime(start), None, Some(duration) ) => {
-                match start {
-                    IcalDateTime::Utc { date_time } => Ok(EventTimeRange::DateTime { start: date_time, end: date_time + duration.duration }),
-                    IcalDateTime::Floating { date_time } => Ok(EventTimeRange::FloatingDateTime { start: date_time, end: date_time + duration.duration }),
-                    IcalDateTime::TimeZone { date_time, tzid } => {
-                        let timezone = timezone_map.get(&tzid).ok_or(Error::InvalidTimezone)?;
-                        Ok(EventTimeRange::DateTime { start: timezone.to_utc(date_time)?, end: timezone.to_utc(date_time + duration.duration)? })
-                    }
-                }
+            ( TimeValue::DateTime(start), None, duration ) => {
+                let duration = duration.unwrap_or(ICalDuration { duration: options.default_duration.date_time });
+                let end = start.checked_add_duration(&duration).ok_or(Error::InvalidDateTime)?;
+                date_time_range(start, end, timezone_map)
             },
+            ( TimeValue::DateTime(start), Some(TimeValue::DateTime(end)), None ) => date_time_range(start, end, timezone_map),
             _ => Err(Error::InvalidTimeRange)
+        }?;
+
+        if range.end_before_start() {
+            return Err(Error::EndBeforeStart);
         }
+        Ok(range)
     }
 }
 
-pub struct Event {
+/// Event status from STATUS. Known values match in any case, and others are kept as written in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventStatus {
+    Tentative,
+    Confirmed,
+    Cancelled,
+    Other(String),
+}
+
+impl From<String> for EventStatus {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "TENTATIVE" => EventStatus::Tentative,
+            "CONFIRMED" => EventStatus::Confirmed,
+            "CANCELLED" => EventStatus::Cancelled,
+            _ => EventStatus::Other(value),
+        }
+    }
+}
+
+impl EventStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventStatus::Tentative => "TENTATIVE",
+            EventStatus::Confirmed => "CONFIRMED",
+            EventStatus::Cancelled => "CANCELLED",
+            EventStatus::Other(value) => value,
+        }
+    }
+}
+
+/// Access classification from CLASS, matched in any case. Unknown IANA and X- tokens are kept as `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum EventClass {
+    #[default]
+    Public,
+    Private,
+    Confidential,
+    Other(String),
+}
+
+impl From<String> for EventClass {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "PUBLIC" => EventClass::Public,
+            "PRIVATE" => EventClass::Private,
+            "CONFIDENTIAL" => EventClass::Confidential,
+            _ => EventClass::Other(value),
+        }
+    }
+}
+
+impl EventClass {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventClass::Public => "PUBLIC",
+            EventClass::Private => "PRIVATE",
+            EventClass::Confidential => "CONFIDENTIAL",
+            EventClass::Other(value) => value,
+        }
+    }
+}
+
+/// Coarse PRIORITY levels: 1-4 are high, 5 is medium and 6-9 are low.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    /// Groups a PRIORITY value, giving `None` for 0 (undefined) and out-of-range values.
+    pub fn from_value(value: u8) -> Option<Self> {
+        match value {
+            1..=4 => Some(Priority::High),
+            5 => Some(Priority::Medium),
+            6..=9 => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
+/// RELTYPE of a RELATED-TO link, PARENT when the parameter is absent.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RelationType {
+    #[default]
+    Parent,
+    Child,
+    Sibling,
+    Other(String),
+}
+
+impl From<String> for RelationType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "PARENT" => RelationType::Parent,
+            "CHILD" => RelationType::Child,
+            "SIBLING" => RelationType::Sibling,
+            _ => RelationType::Other(value),
+        }
+    }
+}
+
+impl RelationType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RelationType::Parent => "PARENT",
+            RelationType::Child => "CHILD",
+            RelationType::Sibling => "SIBLING",
+            RelationType::Other(value) => value,
+        }
+    }
+}
+
+/// Identity of an event: its UID plus, for overrides, the RECURRENCE-ID instant. Revisions of the same event or
+/// override share a key even when their content differs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EventKey {
+    pub uid: Option<String>,
+    pub recurrence_id: Option<EventInstant>,
+}
+
+/// A link from RELATED-TO to the component with UID `uid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedTo {
+    pub uid: String,
+    pub reltype: RelationType,
+}
+
+/// Whether an event blocks time for free/busy purposes, from TRANSP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transparency {
+    #[default]
+    Opaque,
+    Transparent,
+}
+
+/// An event's times after `Event::times_in`, waiting to be set.
+pub(crate) struct AssumedTimes {
     time: EventTimeRange,
-}
\ No newline at end of file
+    rdates: Vec<EventInstant>,
+    exdates: Vec<EventInstant>,
+    recurrence_id: Option<RecurrenceId>,
+}
+
+pub struct Event {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// Every COMMENT, in order. Text values are unescaped.
+    pub comments: Vec<String>,
+    /// Every CONTACT, in order.
+    pub contacts: Vec<String>,
+    pub related_to: Vec<RelatedTo>,
+    pub time: EventTimeRange,
+    pub alarms: Vec<Alarm>,
+    pub attachments: Vec<IcalAttach>,
+    pub organizer: Option<Organizer>,
+    pub attendees: Vec<Attendee>,
+    pub r_rule: Option<IcalRecur>,
+    /// TZID of DTSTART, used to expand recurrences in the event's local time.
+    pub tzid: Option<String>,
+    /// DTSTART's wall-clock value in `tzid` (midnight for all-day events), the anchor for recurrence expansion.
+    pub local_start: NaiveDateTime,
+    /// Extra instances from RDATE, each as long as the event itself. PERIOD values are rejected, or skipped with a
+    /// warning when parsing leniently.
+    pub rdates: Vec<EventInstant>,
+    pub exdates: Vec<EventInstant>,
+    /// Revision number, 0 when SEQUENCE is absent. The highest wins among events sharing a UID.
+    pub sequence: i32,
+    pub status: Option<EventStatus>,
+    /// When the event was first created, from CREATED.
+    pub created: Option<DateTime<Utc>>,
+    /// When the event was last revised, from LAST-MODIFIED.
+    pub last_modified: Option<DateTime<Utc>>,
+    /// When this copy of the event was produced, from DTSTAMP. Only missing when parsed leniently.
+    pub dtstamp: Option<DateTime<Utc>>,
+    /// PUBLIC when CLASS is absent.
+    pub class: EventClass,
+    /// OPAQUE when TRANSP is absent.
+    pub transp: Transparency,
+    /// PRIORITY from 1 (highest) to 9 (lowest), 0 when undefined or absent.
+    pub priority: u8,
+    /// Set on overrides of a single occurrence of a recurring event.
+    pub recurrence_id: Option<RecurrenceId>,
+    /// Every REQUEST-STATUS, as found on iTIP replies.
+    pub request_statuses: Vec<IcalRequestStatus>,
+    pub extensions: IcalExtensions,
+}
+
+impl Event {
+    /// An event with nothing but a UID, its time and a DTSTAMP of now, for building calendars.
+    /// Set `tzid` and `local_start` as well to write a zoned DTSTART.
+    pub fn new(uid: impl Into<String>, time: EventTimeRange) -> Self {
+        let local_start = match time {
+            EventTimeRange::Date { start, .. } => start.and_time(NaiveTime::MIN),
+            EventTimeRange::DateTime { start, .. } => start.naive_utc(),
+            EventTimeRange::FloatingDateTime { start, .. } => start,
+        };
+        Event {
+            uid: Some(uid.into()),
+            summary: None,
+            description: None,
+            comments: Vec::new(),
+            contacts: Vec::new(),
+            related_to: Vec::new(),
+            time,
+            alarms: Vec::new(),
+            attachments: Vec::new(),
+            organizer: None,
+            attendees: Vec::new(),
+            r_rule: None,
+            tzid: None,
+            local_start,
+            rdates: Vec::new(),
+            exdates: Vec::new(),
+            sequence: 0,
+            status: None,
+            created: None,
+            last_modified: None,
+            dtstamp: Some(Utc::now()),
+            class: EventClass::default(),
+            transp: Transparency::default(),
+            priority: 0,
+            recurrence_id: None,
+            request_statuses: Vec::new(),
+            extensions: HashMap::new(),
+        }
+    }
+
+    /// The VEVENT for this event. UTC times are written in `tzid` when it can be resolved, and in UTC otherwise.
+    pub fn to_component(&self, timezone_map: &TimezoneMap) -> IcalEvent {
+        let zone = self.tzid.as_deref().filter(|tzid| knows_timezone(timezone_map, tzid));
+        let zoned = |name: &str, date_time: NaiveDateTime, tzid: &str| new_property(name, &[("TZID", tzid)], IcalDateTime::Floating { date_time }.to_ical());
+        let instant = |name: &str, instant: EventInstant| match instant {
+            EventInstant::Date(date) => new_property(name, &[("VALUE", "DATE")], IcalDate { date }.to_ical()),
+            EventInstant::DateTime(date_time) => match zone.and_then(|tzid| Some((tzid, utc_to_zoned(date_time, tzid, timezone_map)?))) {
+                Some((tzid, local)) => zoned(name, local, tzid),
+                None => new_property(name, &[], IcalDateTime::Utc { date_time }.to_ical()),
+            },
+            EventInstant::FloatingDateTime(date_time) => new_property(name, &[], IcalDateTime::Floating { date_time }.to_ical()),
+        };
+        let text = |name: &str, value: &str| new_property(name, &[], IcalText::new(value).to_ical());
+        let timestamp = |name: &str, date_time: DateTime<Utc>| new_property(name, &[], IcalDateTime::Utc { date_time }.to_ical());
+
+        let mut properties = Vec::new();
+        properties.extend(self.uid.as_ref().map(|uid| new_property("UID", &[], uid.clone())));
+        properties.extend(self.dtstamp.map(|dtstamp| timestamp("DTSTAMP", dtstamp)));
+        let end = match self.time {
+            EventTimeRange::Date { end, .. } => EventInstant::Date(end),
+            EventTimeRange::DateTime { end, .. } => EventInstant::DateTime(end),
+            EventTimeRange::FloatingDateTime { end, .. } => EventInstant::FloatingDateTime(end),
+        };
+        // The local start is kept exactly as written, even where it falls in a gap, unless it no longer names the start.
+        properties.push(match (zone, &self.time) {
+            (Some(tzid), EventTimeRange::DateTime { start, .. }) if zoned_to_utc(self.local_start, tzid, timezone_map).ok() == Some(*start) => {
+                zoned("DTSTART", self.local_start, tzid)
+            },
+            _ => instant("DTSTART", self.time.start()),
+        });
+        properties.push(instant("DTEND", end));
+        properties.extend(self.r_rule.as_ref().map(|recur| new_property("RRULE", &[], recur.to_ical())));
+        properties.extend(self.rdates.iter().map(|rdate| instant("RDATE", *rdate)));
+        properties.extend(self.exdates.iter().map(|exdate| instant("EXDATE", *exdate)));
+        if let Some(id) = self.recurrence_id {
+            let mut property = instant("RECURRENCE-ID", id.instant);
+            if id.range == RecurrenceRange::ThisAndFuture {
+                property.params.get_or_insert_with(Vec::new).push(("RANGE".to_string(), vec!["THISANDFUTURE".to_string()]));
+            }
+            properties.push(property);
+        }
+
+        properties.extend(self.summary.as_deref().map(|summary| text("SUMMARY", summary)));
+        properties.extend(self.description.as_deref().map(|description| text("DESCRIPTION", description)));
+        properties.extend(self.comments.iter().map(|comment| text("COMMENT", comment)));
+        properties.extend(self.contacts.iter().map(|contact| text("CONTACT", contact)));
+        properties.extend(self.related_to.iter().map(|related| match related.reltype {
+            RelationType::Parent => new_property("RELATED-TO", &[], related.uid.clone()),
+            ref reltype => new_property("RELATED-TO", &[("RELTYPE", reltype.as_str())], related.uid.clone()),
+        }));
+        properties.extend(self.status.as_ref().map(|status| new_property("STATUS", &[], status.as_str().to_string())));
+        if self.class != EventClass::default() {
+            properties.push(new_property("CLASS", &[], self.class.as_str().to_string()));
+        }
+        if self.transp == Transparency::Transparent {
+            properties.push(new_property("TRANSP", &[], "TRANSPARENT".to_string()));
+        }
+        if self.priority != 0 {
+            properties.push(new_property("PRIORITY", &[], self.priority.to_string()));
+        }
+        if self.sequence != 0 {
+            properties.push(new_property("SEQUENCE", &[], self.sequence.to_string()));
+        }
+        properties.extend(self.created.map(|created| timestamp("CREATED", created)));
+        properties.extend(self.last_modified.map(|last_modified| timestamp("LAST-MODIFIED", last_modified)));
+        properties.extend(self.organizer.as_ref().map(Organizer::to_property));
+        properties.extend(self.attendees.iter().map(Attendee::to_property));
+        properties.extend(self.attachments.iter().map(IcalAttach::to_property));
+        properties.extend(self.request_statuses.iter().map(|status| new_property("REQUEST-STATUS", &[], status.to_ical())));
+
+        let mut extensions = self.extensions.iter().collect::<Vec<_>>();
+        extensions.sort_by_key(|(name, _)| name.as_str());
+        properties.extend(extensions.into_iter().flat_map(|(_, props)| props.iter().cloned()));
+
+        IcalEvent { properties, alarms: self.alarms.iter().map(Alarm::to_component).collect() }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status == Some(EventStatus::Cancelled)
+    }
+
+    pub fn key(&self) -> EventKey {
+        EventKey { uid: self.uid.clone(), recurrence_id: self.recurrence_id.map(|id| id.instant) }
+    }
+
+    pub fn priority_level(&self) -> Option<Priority> {
+        Priority::from_value(self.priority)
+    }
+
+    /// Reads the event's floating times as wall-clock times in `tzid`, making them UTC. Other times are left alone,
+    /// and the event only takes `tzid` for recurrence expansion if its start was floating.
+    pub fn assume_timezone(&mut self, tzid: &str, timezone_map: &TimezoneMap) -> Result<(), Error> {
+        let times = self.times_in(tzid, timezone_map)?;
+        self.set_times(tzid, times);
+        Ok(())
+    }
+
+    /// The event's times with floating ones read in `tzid`, for `assume_timezone`, without changing the event.
+    pub(crate) fn times_in(&self, tzid: &str, timezone_map: &TimezoneMap) -> Result<AssumedTimes, Error> {
+        let instant = |instant: EventInstant| match instant {
+            EventInstant::FloatingDateTime(date_time) => zoned_to_utc(date_time, tzid, timezone_map).map(EventInstant::DateTime),
+            instant => Ok(instant),
+        };
+
+        let time = match self.time {
+            EventTimeRange::FloatingDateTime { start, end } => {
+                EventTimeRange::DateTime { start: zoned_to_utc(start, tzid, timezone_map)?, end: zoned_to_utc(end, tzid, timezone_map)? }
+            },
+            ref time => time.clone(),
+        };
+        let rdates = self.rdates.iter().map(|rdate| instant(*rdate)).collect::<Result<Vec<_>, _>>()?;
+        let exdates = self.exdates.iter().map(|exdate| instant(*exdate)).collect::<Result<Vec<_>, _>>()?;
+        let recurrence_id = self.recurrence_id.map(|id| instant(id.instant).map(|instant| RecurrenceId { instant, ..id })).transpose()?;
+        Ok(AssumedTimes { time, rdates, exdates, recurrence_id })
+    }
+
+    pub(crate) fn set_times(&mut self, tzid: &str, times: AssumedTimes) {
+        if matches!(self.time, EventTimeRange::FloatingDateTime { .. }) {
+            self.tzid = Some(tzid.to_string());
+        }
+        self.time = times.time;
+        self.rdates = times.rdates;
+        self.exdates = times.exdates;
+        self.recurrence_id = times.recurrence_id;
+    }
+
+    /// Whether DTSTART is a DATE. See `spans_whole_days` for all-day events written with times.
+    pub fn is_all_day(&self) -> bool {
+        matches!(self.time, EventTimeRange::Date { .. })
+    }
+
+    /// Like `is_all_day`, but also true for DATE-TIME events running from midnight to a later midnight in their own
+    /// time zone, as some producers write all-day events. UTC and floating times are taken as they are.
+    pub fn spans_whole_days(&self, timezone_map: &TimezoneMap) -> bool {
+        let local_end = match self.time {
+            EventTimeRange::Date { .. } => return true,
+            EventTimeRange::FloatingDateTime { end, .. } => Some(end),
+            EventTimeRange::DateTime { end, .. } => match &self.tzid {
+                Some(tzid) => utc_to_zoned(end, tzid, timezone_map),
+                None => Some(end.naive_utc()),
+            },
+        };
+        local_end.is_some_and(|end| end > self.local_start && self.local_start.time() == NaiveTime::MIN && end.time() == NaiveTime::MIN)
+    }
+
+    /// Parses a single VEVENT on its own, without a surrounding VCALENDAR. The `BEGIN:VEVENT` and `END:VEVENT`
+    /// lines may also be left out. Parsing is strict, and anything other than exactly one event is `InvalidEvent`.
+    pub fn parse(input: &str, timezone_map: &TimezoneMap) -> Result<Self, Error> {
+        let input = input.trim();
+        let begins = input.get(..12).is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VEVENT"));
+        let ends = input.len().checked_sub(10).and_then(|start| input.get(start..)).is_some_and(|line| line.eq_ignore_ascii_case("END:VEVENT"));
+
+        let mut wrapped = String::from("BEGIN:VCALENDAR\r\n");
+        if !begins {
+            wrapped.push_str("BEGIN:VEVENT\r\n");
+        }
+        wrapped.push_str(input);
+        wrapped.push_str("\r\n");
+        if !ends {
+            wrapped.push_str("END:VEVENT\r\n");
+        }
+        wrapped.push_str("END:VCALENDAR\r\n");
+
+        let mut calendar = IcalParser::new(wrapped.as_bytes()).next().ok_or(Error::InvalidEvent)?.map_err(|_| Error::InvalidEvent)?;
+        if calendar.events.len() != 1 {
+            return Err(Error::InvalidEvent);
+        }
+        Event::from_component(calendar.events.remove(0), timezone_map, &ParseOptions::default(), &mut Vec::new())
+    }
+
+    pub fn from_component(component: IcalEvent, timezone_map: &TimezoneMap, options: &ParseOptions, warnings: &mut Vec<Error>) -> Result<Self, Error> {
+        let mut uid = None;
+        let mut summary = None;
+        let mut description = None;
+        let mut comments = Vec::new();
+        let mut contacts = Vec::new();
+        let mut related_to = Vec::new();
+        let mut sequence = 0;
+        let mut status = None;
+        let mut created = None;
+        let mut last_modified = None;
+        let mut dtstamp = None;
+        let mut class = EventClass::default();
+        let mut transp = Transparency::default();
+        let mut priority = 0;
+        let mut start = None;
+        let mut end = None;
+        let mut duration = None;
+        let mut organizer = None;
+        let mut attendees = Vec::new();
+        let mut attachments = Vec::new();
+        let mut r_rule = None;
+        let mut recurrence_id = None;
+        let mut rdates = Vec::new();
+        let mut exdates = Vec::new();
+        let mut request_statuses = Vec::new();
+        let mut extensions = HashMap::new();
+
+        for prop in component.properties {
+            if let Some(value_type) = get_param(&prop, "VALUE") {
+                if let Err(error) = value_type.parse::<ICalTypes>() {
+                    options.recover(error, warnings)?;
+                    continue;
+                }
+            }
+
+            match prop.name.as_str() {
+                "UID" => uid = prop.value,
+                // SUMMARY and DESCRIPTION may appear once, so a repeat is ignored.
+                "SUMMARY" if summary.is_none() => summary = Some(IcalText::try_from(prop)?.unescaped()),
+                "DESCRIPTION" if description.is_none() => description = Some(IcalText::try_from(prop)?.unescaped()),
+                "COMMENT" => comments.push(IcalText::try_from(prop)?.unescaped()),
+                "CONTACT" => contacts.push(IcalText::try_from(prop)?.unescaped()),
+                "RELATED-TO" => {
+                    let reltype = get_param(&prop, "RELTYPE").cloned().map(RelationType::from).unwrap_or_default();
+                    related_to.push(RelatedTo { uid: IcalText::try_from(prop)?.unescaped(), reltype });
+                },
+                "SEQUENCE" => sequence = IcalInteger::try_from(prop)?.value,
+                "STATUS" => status = prop.value.map(EventStatus::from),
+                "CREATED" => created = parse_timestamp(prop, options, warnings)?,
+                "LAST-MODIFIED" => last_modified = parse_timestamp(prop, options, warnings)?,
+                "DTSTAMP" => dtstamp = parse_timestamp(prop, options, warnings)?,
+                "PRIORITY" => match u8::try_from(IcalInteger::try_from(prop)?.value) {
+                    Ok(value @ 0..=9) => priority = value,
+                    _ => options.recover(Error::TypeDecode(ICalTypes::Integer), warnings)?,
+                },
+                "CLASS" => class = prop.value.map(EventClass::from).unwrap_or_default(),
+                "TRANSP" => transp = match prop.value.map(|value| value.to_ascii_uppercase()).as_deref() {
+                    Some("TRANSPARENT") => Transparency::Transparent,
+                    Some("OPAQUE") | None => Transparency::Opaque,
+                    Some(_) => {
+                        options.recover(Error::TypeDecode(ICalTypes::Text), warnings)?;
+                        Transparency::Opaque
+                    },
+                },
+                "DTSTART" => start = Some(prop),
+                "DTEND" => end = Some(prop),
+                "DURATION" => duration = Some(prop),
+                "ORGANIZER" => organizer = Some(Organizer::try_from(prop)?),
+                "ATTENDEE" => attendees.push(Attendee::try_from(prop)?),
+                "ATTACH" => attachments.push(IcalAttach::try_from(prop)?),
+                "RRULE" => {
+                    let mut recur = IcalRecur::try_from(prop)?;
+                    if let Err(error) = recur.validate() {
+                        options.recover(error, warnings)?;
+                        if !recur.retain_valid() {
+                            continue;
+                        }
+                    }
+                    r_rule = Some(recur);
+                },
+                "RECURRENCE-ID" => recurrence_id = Some(prop),
+                "RDATE" if get_param(&prop, "VALUE").is_some_and(|value| value.eq_ignore_ascii_case("PERIOD")) => options.recover(Error::UnsupportedRecurrence, warnings)?,
+                "RDATE" => rdates.append(&mut parse_instants(prop, timezone_map, options, warnings)?),
+                "EXDATE" => exdates.append(&mut parse_instants(prop, timezone_map, options, warnings)?),
+                "REQUEST-STATUS" => match IcalRequestStatus::try_from(prop) {
+                    Ok(status) => request_statuses.push(status),
+                    Err(error) => options.recover(error, warnings)?,
+                },
+                _ if is_extension(&prop) => extensions.entry(prop.name.clone()).or_insert_with(Vec::new).push(prop),
+                _ => {}
+            }
+        }
+
+        if dtstamp.is_none() {
+            options.recover(Error::MissingProperty("DTSTAMP".to_string()), warnings)?;
+        }
+
+        let start = start.ok_or(Error::InvalidTimeRange)?;
+        let tzid = get_param(&start, "TZID").cloned();
+        // Any warning about DTSTART is recorded once, when the range is resolved below.
+        let local_start = parse_time_value(start.clone(), options, &mut Vec::new())?.wall_clock();
+        let timing = RawTiming { start, end, duration };
+        let time = timing.get_time_range(timezone_map, options, warnings)?;
+        let recurrence_id = recurrence_id.map(|prop| RecurrenceId::from_property(prop, timezone_map, options, warnings)).transpose()?;
+        let alarms = component.alarms.into_iter().map(Alarm::try_from).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Event { uid, summary, description, comments, contacts, related_to, time, alarms, attachments, organizer, attendees, r_rule, tzid, local_start, rdates, exdates, sequence, status, created, last_modified, dtstamp, class, transp, priority, recurrence_id, request_statuses, extensions })
+    }
+}
+
+/// One occurrence of a recurring event, taken from either the master or the override that replaced it.
+pub struct Occurrence<'a> {
+    pub event: &'a Event,
+    pub time: EventTimeRange,
+}
+
+/// A master event together with the overrides that replace some of its occurrences.
+pub struct RecurringEvent {
+    pub master: Event,
+    pub overrides: Vec<Event>,
+    timezone: Option<Timezone>,
+    max_iterations: usize,
+}
+
+impl RecurringEvent {
+    pub fn new(master: Event, overrides: Vec<Event>, timezone_map: &TimezoneMap) -> Self {
+        let timezone = master.tzid.as_ref().and_then(|tzid| timezone_map.get(tzid)).cloned();
+        RecurringEvent { master, overrides, timezone, max_iterations: DEFAULT_MAX_ITERATIONS }
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn with_options(self, options: &ParseOptions) -> Self {
+        self.max_iterations(options.max_iterations)
+    }
+
+    pub fn exdates(&self) -> &[EventInstant] {
+        &self.master.exdates
+    }
+
+    /// Occurrences starting within `[start, end]`, sorted by start. Dates count from midnight UTC and floating times
+    /// are treated as UTC. RDATE instances are added to the RRULE's, instances listed in EXDATE are dropped, and
+    /// instances matched by an override's RECURRENCE-ID are replaced by that override wherever it has been moved to.
+    ///
+    /// An override with `RANGE=THISANDFUTURE` also replaces every later instance, up to the next such override: each
+    /// one takes the override's properties and length, moved by the same offset the override applies to its own
+    /// instance. Single-instance overrides and EXDATEs still win over a future-range override.
+    ///
+    /// Fails with `IterationLimit` rather than returning a partial list if the RRULE's expansion hits the cap.
+    pub fn occurrences_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Occurrence<'_>>, Error> {
+        self.as_ref().occurrences_between(start, end)
+    }
+
+    pub fn as_ref(&self) -> RecurringEventRef<'_> {
+        RecurringEventRef { master: &self.master, overrides: self.overrides.iter().collect(), timezone: self.timezone.as_ref(), max_iterations: self.max_iterations }
+    }
+}
+
+/// A `RecurringEvent` borrowing its events, as grouped by `Calendar::recurring_events`.
+pub struct RecurringEventRef<'a> {
+    pub master: &'a Event,
+    pub overrides: Vec<&'a Event>,
+    timezone: Option<&'a Timezone>,
+    max_iterations: usize,
+}
+
+impl<'a> RecurringEventRef<'a> {
+    pub fn new(master: &'a Event, overrides: Vec<&'a Event>, timezone_map: &'a TimezoneMap) -> Self {
+        let timezone = master.tzid.as_ref().and_then(|tzid| timezone_map.get(tzid));
+        RecurringEventRef { master, overrides, timezone, max_iterations: DEFAULT_MAX_ITERATIONS }
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn with_options(self, options: &ParseOptions) -> Self {
+        self.max_iterations(options.max_iterations)
+    }
+
+    /// See `RecurringEvent::occurrences_between`.
+    pub fn occurrences_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Occurrence<'a>>, Error> {
+        series_occurrences(self.master, &self.overrides, self.timezone, self.max_iterations, start, end)
+    }
+}
+
+fn series_occurrences<'a>(master: &'a Event, overrides: &[&'a Event], timezone: Option<&Timezone>, max_iterations: usize, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Occurrence<'a>>, Error> {
+    let overridden = overrides.iter().filter_map(|event| event.recurrence_id).map(|id| id.instant).collect::<Vec<_>>();
+    let mut future = overrides.iter()
+        .filter_map(|event| event.recurrence_id.filter(|id| id.range == RecurrenceRange::ThisAndFuture).map(|id| (id.instant.to_utc(), *event)))
+        .collect::<Vec<_>>();
+    future.sort_by_key(|(anchor, _)| *anchor);
+    let in_window = |time: &EventTimeRange| (start..=end).contains(&time.start_instant());
+
+    let mut occurrences = Vec::new();
+    for time in master_occurrences(master, timezone, max_iterations, start, end)? {
+        let instant = time.start();
+        if master.exdates.contains(&instant) || overridden.contains(&instant) {
+            continue;
+        }
+
+        let occurrence = match future.iter().rev().find(|(anchor, _)| *anchor <= instant.to_utc()) {
+            Some((anchor, event)) => Occurrence { event, time: event.time.shifted(instant.to_utc() - *anchor).ok_or(Error::InvalidDateTime)? },
+            None => Occurrence { event: master, time },
+        };
+        if in_window(&occurrence.time) {
+            occurrences.push(occurrence);
+        }
+    }
+    for event in overrides {
+        if in_window(&event.time) {
+            occurrences.push(Occurrence { event, time: event.time.clone() });
+        }
+    }
+
+    occurrences.sort_by_key(|occurrence| occurrence.time.start_instant());
+    Ok(occurrences)
+}
+
+/// The master's own instances: its RRULE expansion, or just itself without one, plus any RDATEs in the window.
+fn master_occurrences(master: &Event, timezone: Option<&Timezone>, max_iterations: usize, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<EventTimeRange>, Error> {
+    let mut occurrences = recur_occurrences(master, timezone, max_iterations, start, end)?;
+    for rdate in &master.rdates {
+        if (start..=end).contains(&rdate.to_utc()) && !occurrences.iter().any(|time| time.start() == *rdate) {
+            occurrences.push(master.time.starting_at(*rdate).ok_or(Error::InvalidDateTime)?);
+        }
+    }
+    Ok(occurrences)
+}
+
+fn recur_occurrences(master: &Event, timezone: Option<&Timezone>, max_iterations: usize, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<EventTimeRange>, Error> {
+    let Some(recur) = &master.r_rule else {
+        return Ok(vec![master.time.clone()]);
+    };
+
+    recur.validate_until(matches!(master.time, EventTimeRange::Date { .. }))?;
+
+    let (window_start, window_end) = (start.naive_utc(), end.naive_utc());
+    let (occurrences, limit_reached) = match master.time {
+        EventTimeRange::Date { start: first, end: last } => {
+            let length = last - first;
+            let mut starts = recur.occurrences_between(master.local_start, window_start, window_end)?.max_iterations(max_iterations);
+            let occurrences = starts.by_ref().map(|start| EventTimeRange::Date { start: start.date(), end: start.date() + length }).collect();
+            (occurrences, starts.limit_reached())
+        },
+        EventTimeRange::FloatingDateTime { start: first, end: last } => {
+            let length = last - first;
+            let mut starts = recur.occurrences_between(master.local_start, window_start, window_end)?.max_iterations(max_iterations);
+            let occurrences = starts.by_ref().map(|start| EventTimeRange::FloatingDateTime { start, end: start + length }).collect();
+            (occurrences, starts.limit_reached())
+        },
+        EventTimeRange::DateTime { start: first, end: last } => {
+            let length = last - first;
+            match timezone {
+                Some(timezone) => {
+                    let mut starts = recur.occurrences_between_in(master.local_start, timezone, start, end)?.max_iterations(max_iterations);
+                    let occurrences = starts.by_ref().map(|start| EventTimeRange::DateTime { start, end: start + length }).collect();
+                    (occurrences, starts.limit_reached())
+                },
+                // A TZID known only to chrono-tz: expand in local time over a window wide enough for any offset, and
+                // convert each start as DTSTART was.
+                None if master.tzid.as_deref().is_some_and(|tzid| knows_timezone(&TimezoneMap::new(), tzid)) => {
+                    let tzid = master.tzid.as_deref().unwrap_or_default();
+                    let slack = Duration::days(2);
+                    let local_start = window_start.checked_sub_signed(slack).unwrap_or(NaiveDateTime::MIN);
+                    let local_end = window_end.checked_add_signed(slack).unwrap_or(NaiveDateTime::MAX);
+                    let mut starts = recur.occurrences_between(master.local_start, local_start, local_end)?.max_iterations(max_iterations).until_slack(slack);
+                    let until = recur.utc_until();
+
+                    let mut occurrences = Vec::new();
+                    for local in starts.by_ref() {
+                        let start_utc = zoned_to_utc(local, tzid, &TimezoneMap::new())?;
+                        if (start..=end).contains(&start_utc) && until.is_none_or(|until| start_utc <= until) {
+                            occurrences.push(EventTimeRange::DateTime { start: start_utc, end: start_utc + length });
+                        }
+                    }
+                    (occurrences, starts.limit_reached())
+                },
+                // UTC starts, and TZIDs read as UTC by `MissingTimezonePolicy::Utc`.
+                None => {
+                    let mut starts = recur.occurrences_between(master.local_start, window_start, window_end)?.max_iterations(max_iterations);
+                    let occurrences = starts.by_ref().map(|start| EventTimeRange::DateTime { start: start.and_utc(), end: start.and_utc() + length }).collect();
+                    (occurrences, starts.limit_reached())
+                },
+            }
+        },
+    };
+
+    if limit_reached {
+        return Err(Error::IterationLimit);
+    }
+    Ok(occurrences)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{timezone::tests::test_timezones, DefaultDurationPolicy};
+
+    use super::*;
+
+    fn event(input: &str) -> Result<Event, Error> {
+        let calendar = IcalParser::new(input.as_bytes()).next().unwrap().unwrap();
+        let component = calendar.events.into_iter().next().unwrap();
+        Event::from_component(component, &TimezoneMap::new(), &ParseOptions::default(), &mut Vec::new())
+    }
+
+    /// Parses the first VEVENT in `input` with `strict: false`, returning the warnings it recorded.
+    fn lenient_event(input: &str) -> (Event, Vec<Error>) {
+        let component = IcalParser::new(input.as_bytes()).next().unwrap().unwrap().events.remove(0);
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let mut warnings = Vec::new();
+        let event = Event::from_component(component, &TimezoneMap::new(), &options, &mut warnings).unwrap();
+        (event, warnings)
+    }
+
+    #[test]
+    fn parses_standalone_events() {
+        let body = "UID:abc@example.com\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nSUMMARY:Standup\r\n";
+        for input in [format!("BEGIN:VEVENT\r\n{body}END:VEVENT\r\n"), body.to_string(), format!("\n{}", body.replace("\r\n", "\n"))] {
+            let event = Event::parse(&input, &TimezoneMap::new()).unwrap();
+            assert_eq!(event.uid.as_deref(), Some("abc@example.com"));
+            assert_eq!(event.summary.as_deref(), Some("Standup"));
+        }
+
+        let two = format!("BEGIN:VEVENT\r\n{body}END:VEVENT\r\nBEGIN:VEVENT\r\n{body}END:VEVENT");
+        assert_eq!(Event::parse(&two, &TimezoneMap::new()).err(), Some(Error::InvalidEvent));
+    }
+
+    #[test]
+    fn normalizes_ranges_to_utc() {
+        let zone = test_timezones().remove("Test").unwrap();
+
+        let at = |day: u32, hour: u32| NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap();
+        let floating = EventTimeRange::FloatingDateTime { start: at(1, 9), end: at(1, 10) };
+        assert_eq!(floating.to_utc(&zone), Ok(EventTimeRange::DateTime { start: at(1, 8).and_utc(), end: at(1, 9).and_utc() }));
+
+        let utc = EventTimeRange::DateTime { start: at(1, 9).and_utc(), end: at(1, 10).and_utc() };
+        assert_eq!(utc.to_utc(&zone), Ok(utc.clone()));
+
+        let date = EventTimeRange::Date { start: at(1, 0).date(), end: at(2, 0).date() };
+        assert_eq!(date.to_utc(&zone), Ok(EventTimeRange::DateTime { start: at(1, 0).and_utc(), end: at(2, 0).and_utc() }));
+    }
+
+    #[test]
+    fn value_types_map_to_range_variants() {
+        let timezones = test_timezones();
+        let range = |lines: &str| Event::parse(&format!("DTSTAMP:20240101T000000Z\r\n{lines}"), &timezones).map(|event| event.time);
+
+        let day = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let at = |hour| day(1).and_hms_opt(hour, 0, 0).unwrap();
+        let date = Ok(EventTimeRange::Date { start: day(1), end: day(2) });
+        let utc = Ok(EventTimeRange::DateTime { start: at(9).and_utc(), end: at(10).and_utc() });
+        let floating = Ok(EventTimeRange::FloatingDateTime { start: at(9), end: at(10) });
+
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\n"), date);
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\nDTEND;VALUE=DATE:20240102\r\n"), date);
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\nDURATION:P1D\r\n"), date);
+        assert_eq!(range("DTSTART;TZID=Test;VALUE=DATE:20240101\r\nDTEND;TZID=Test;VALUE=DATE:20240102\r\n"), date);
+
+        assert_eq!(range("DTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\n"), utc);
+        assert_eq!(range("DTSTART:20240101T090000Z\r\nDURATION:PT1H\r\n"), utc);
+        assert_eq!(range("DTSTART;TZID=Test:20240101T100000\r\nDTEND;TZID=Test:20240101T110000\r\n"), utc);
+        assert_eq!(range("DTSTART;TZID=Test:20240101T100000\r\nDURATION:PT1H\r\n"), utc);
+
+        assert_eq!(range("DTSTART:20240101T090000\r\nDTEND:20240101T100000\r\n"), floating);
+        assert_eq!(range("DTSTART:20240101T090000\r\nDURATION:PT1H\r\n"), floating);
+
+        assert_eq!(range("DTSTART:20240101T090000Z\r\nDTEND;VALUE=DATE:20240102\r\n"), Err(Error::InvalidTimeRange));
+        assert_eq!(range("DTSTART:20240101T090000Z\r\nDTEND;TZID=Test:20240101T110000\r\n"), utc);
+        assert_eq!(range("DTSTART;TZID=Test:20240101T100000\r\nDTEND:20240101T100000Z\r\n"), utc);
+        assert_eq!(range("DTSTART:20240101T090000\r\nDTEND:20240101T100000Z\r\n"), Err(Error::InvalidDateTime));
+        assert_eq!(range("DTSTART;TZID=Test:20240101T100000\r\nDTEND:20240101T100000\r\n"), Err(Error::InvalidDateTime));
+        assert_eq!(range("DTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\nDURATION:PT1H\r\n"), Err(Error::InvalidTimeRange));
+    }
+
+    #[test]
+    fn detects_all_day_events() {
+        let timezones = test_timezones();
+        let all_day = |lines: &str| {
+            let event = Event::parse(&format!("DTSTAMP:20240101T000000Z\r\n{lines}"), &timezones).unwrap();
+            (event.is_all_day(), event.spans_whole_days(&timezones))
+        };
+
+        assert_eq!(all_day("DTSTART;VALUE=DATE:20240101\r\n"), (true, true));
+        assert_eq!(all_day("DTSTART;TZID=Test:20240101T000000\r\nDTEND;TZID=Test:20240103T000000\r\n"), (false, true));
+        assert_eq!(all_day("DTSTART:20240101T000000\r\nDURATION:P1D\r\n"), (false, true));
+        assert_eq!(all_day("DTSTART:20240101T000000Z\r\nDTEND:20240102T000000Z\r\n"), (false, true));
+        assert_eq!(all_day("DTSTART;TZID=Test:20240101T010000\r\nDTEND;TZID=Test:20240102T010000\r\n"), (false, false));
+        assert_eq!(all_day("DTSTART;TZID=Test:20240101T000000\r\n"), (false, false));
+    }
+
+    #[test]
+    fn formats_ranges_in_a_zone() {
+        let zone = test_timezones().remove("Test").unwrap();
+
+        let at = |day: u32, hour: u32| NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap();
+        let utc = EventTimeRange::DateTime { start: at(1, 9).and_utc(), end: at(1, 10).and_utc() };
+        assert_eq!(utc.format(&zone, "%Y-%m-%d %H:%M %z").unwrap(), "2024-01-01 10:00 +0100 - 2024-01-01 11:00 +0100");
+
+        let floating = EventTimeRange::FloatingDateTime { start: at(1, 9), end: at(1, 10) };
+        assert_eq!(floating.format(&zone, "%H:%M").unwrap(), "09:00 - 10:00");
+        assert_eq!(floating.format(&zone, "%H:%M %z"), Err(Error::InvalidFormat));
+
+        let date = EventTimeRange::Date { start: at(1, 0).date(), end: at(3, 0).date() };
+        assert_eq!(date.format(&zone, "%H:%M").unwrap(), "2024-01-01 - 2024-01-02");
+    }
+
+    #[test]
+    fn negative_durations_end_before_start() {
+        let parse = |duration: &str| Event::parse(&format!("DTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nDURATION:{duration}\r\n"), &TimezoneMap::new()).map(|event| event.time);
+        assert_eq!(parse("-PT1H"), Err(Error::EndBeforeStart));
+        assert_eq!(parse("-PT0S").map(|time| time.start_instant()), Ok(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap().and_utc()));
+        let all_day = Event::parse("DTSTAMP:20240101T000000Z\r\nDTSTART;VALUE=DATE:20240101\r\nDURATION:-P1D\r\n", &TimezoneMap::new());
+        assert_eq!(all_day.err(), Some(Error::EndBeforeStart));
+    }
+
+    #[test]
+    fn inclusive_end_date_drops_the_exclusive_day() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let range = |dates: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\n{dates}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).unwrap().time;
+
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\nDTEND;VALUE=DATE:20240102").inclusive_end_date(), Some(date(1)));
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\nDTEND;VALUE=DATE:20240104").inclusive_end_date(), Some(date(3)));
+        assert_eq!(range("DTSTART;VALUE=DATE:20240101\r\nDTEND;VALUE=DATE:20240101").inclusive_end_date(), Some(date(1)));
+        assert_eq!(range("DTSTART:20240101T090000Z").inclusive_end_date(), None);
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn falls_back_to_iana_zones_without_vtimezone() {
+        let at = |day, hour, minute| NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap().and_utc();
+        let range = |start: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;TZID={start}\r\nDURATION:PT1H\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).map(|event| event.time);
+
+        assert_eq!(range("America/New_York:20240301T090000"), Ok(EventTimeRange::DateTime { start: at(1, 14, 0), end: at(1, 15, 0) }));
+        // 02:30 doesn't exist on March 10th, so it's read with the EST offset from before the gap. The end, 03:30 EDT,
+        // is the same instant.
+        assert_eq!(range("America/New_York:20240310T023000"), Ok(EventTimeRange::DateTime { start: at(10, 7, 30), end: at(10, 7, 30) }));
+        assert_eq!(range("Mars/Olympus_Mons:20240301T090000"), Err(Error::InvalidTimezone));
+
+        // An embedded VTIMEZONE resolves the gap the same way.
+        let timezones = TimezoneMap::from(HashMap::from([("America/New_York".to_string(), crate::timezone::tests::new_york())]));
+        let component = IcalParser::new("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;TZID=America/New_York:20240310T023000\r\nDURATION:PT1H\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n".as_bytes()).next().unwrap().unwrap().events.remove(0);
+        let embedded = Event::from_component(component, &timezones, &ParseOptions::default(), &mut Vec::new()).unwrap();
+        assert_eq!(embedded.time, range("America/New_York:20240310T023000").unwrap());
+    }
+
+    #[test]
+    fn keywords_ignore_case() {
+        let event = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nSTATUS:cancelled\r\nCLASS:Private\r\nTRANSP:transparent\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(event.status, Some(EventStatus::Cancelled));
+        assert_eq!(event.class, EventClass::Private);
+        assert_eq!(event.transp, Transparency::Transparent);
+        assert_eq!(EventClass::from("x-Custom".to_string()), EventClass::Other("x-Custom".to_string()));
+    }
+
+    #[test]
+    fn parses_sequence_and_status() {
+        let event = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:abc@example.com\r\nSEQUENCE:3\r\nSTATUS:CANCELLED\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(event.uid.as_deref(), Some("abc@example.com"));
+        assert_eq!(event.sequence, 3);
+        assert!(event.is_cancelled());
+    }
+
+    #[test]
+    fn sequence_defaults_to_zero() {
+        let event = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(event.sequence, 0);
+        assert_eq!(event.status, None);
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        let parsed = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nCREATED:20231201T080000Z\r\nLAST-MODIFIED:20231215T120000Z\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let at = |month, day, hour| NaiveDate::from_ymd_opt(2023, month, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        assert_eq!(parsed.created, Some(at(12, 1, 8)));
+        assert_eq!(parsed.last_modified, Some(at(12, 15, 12)));
+        assert_eq!(parsed.dtstamp, Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_time(NaiveTime::MIN).and_utc()));
+
+        let floating = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nLAST-MODIFIED:20231215T120000\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(floating).err(), Some(Error::InvalidDateTime));
+
+        let (parsed, warnings) = lenient_event(floating);
+        assert_eq!(parsed.last_modified, None);
+        assert_eq!(warnings, vec![Error::InvalidDateTime]);
+    }
+
+    #[test]
+    fn malformed_request_statuses_are_dropped_when_lenient() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nREQUEST-STATUS:2.0;Success\r\nREQUEST-STATUS:OK\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::TypeDecode(ICalTypes::Text)));
+
+        let (parsed, warnings) = lenient_event(input);
+        assert_eq!(parsed.request_statuses, vec![IcalRequestStatus { code: "2.0".to_string(), description: "Success".to_string(), extra: None }]);
+        assert_eq!(warnings, vec![Error::TypeDecode(ICalTypes::Text)]);
+    }
+
+    #[test]
+    fn dtstamp_is_required_when_strict() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::MissingProperty("DTSTAMP".to_string())));
+
+        let (event, warnings) = lenient_event(input);
+        assert_eq!(event.dtstamp, None);
+        assert_eq!(warnings, vec![Error::MissingProperty("DTSTAMP".to_string())]);
+    }
+
+    #[test]
+    fn value_date_drops_stray_times() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;VALUE=date:20240101T090000\r\nDTEND;VALUE=DATE:20240102\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::TypeDecode(ICalTypes::Date)));
+
+        let (event, warnings) = lenient_event(input);
+        let day = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        assert_eq!(event.time, EventTimeRange::Date { start: day(1), end: day(2) });
+        assert_eq!(warnings, vec![Error::TypeDecode(ICalTypes::Date)]);
+    }
+
+    #[test]
+    fn parses_priority() {
+        let priority = |line: &str, options: &ParseOptions| {
+            let input = format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\n{line}END:VEVENT\r\nEND:VCALENDAR\r\n");
+            let component = IcalParser::new(input.as_bytes()).next().unwrap().unwrap().events.remove(0);
+            Event::from_component(component, &TimezoneMap::new(), options, &mut Vec::new()).map(|event| (event.priority, event.priority_level()))
+        };
+        let strict = ParseOptions::default();
+        assert_eq!(priority("", &strict), Ok((0, None)));
+        assert_eq!(priority("PRIORITY:1\r\n", &strict), Ok((1, Some(Priority::High))));
+        assert_eq!(priority("PRIORITY:5\r\n", &strict), Ok((5, Some(Priority::Medium))));
+        assert_eq!(priority("PRIORITY:9\r\n", &strict), Ok((9, Some(Priority::Low))));
+        assert_eq!(priority("PRIORITY:10\r\n", &strict), Err(Error::TypeDecode(ICalTypes::Integer)));
+
+        let lenient = ParseOptions { strict: false, ..ParseOptions::default() };
+        assert_eq!(priority("PRIORITY:-1\r\n", &lenient), Ok((0, None)));
+    }
+
+    #[test]
+    fn keeps_every_comment_and_contact() {
+        let parsed = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nSUMMARY:Planning\\, Q1\r\nSUMMARY:Ignored\r\nDESCRIPTION:Agenda\r\nCOMMENT:Bring laptops\r\nCOMMENT:Room 4 is booked\r\nCONTACT:Jim Dolittle\\, ABC Industries\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(parsed.summary.as_deref(), Some("Planning, Q1"));
+        assert_eq!(parsed.description.as_deref(), Some("Agenda"));
+        assert_eq!(parsed.comments, vec!["Bring laptops", "Room 4 is booked"]);
+        assert_eq!(parsed.contacts, vec!["Jim Dolittle, ABC Industries"]);
+    }
+
+    #[test]
+    fn unfolds_folded_text() {
+        let parsed = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nDESCRIPTION:Agenda: review the\r\n  roadmap\\, then plan\r\n\t the next quarter\\nThanks\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(parsed.description.as_deref(), Some("Agenda: review the roadmap, then plan the next quarter\nThanks"));
+    }
+
+    #[test]
+    fn key_ignores_content() {
+        let first = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nDTSTART:20240101T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let revised = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240102T000000Z\r\nUID:weekly\r\nSEQUENCE:1\r\nDTSTART:20240101T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let moved = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nRECURRENCE-ID:20240108T090000Z\r\nDTSTART:20240108T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+
+        assert_eq!(first.key(), revised.key());
+        assert_ne!(first.key(), moved.key());
+        let keys = [&first, &revised, &moved].map(|event| event.key()).into_iter().collect::<HashSet<_>>();
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn parses_related_to() {
+        let parsed = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nRELATED-TO:trip@example.com\r\nRELATED-TO;RELTYPE=SIBLING:flight@example.com\r\nRELATED-TO;RELTYPE=X-DEPENDS-ON:visa@example.com\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        assert_eq!(parsed.related_to, vec![
+            RelatedTo { uid: "trip@example.com".to_string(), reltype: RelationType::Parent },
+            RelatedTo { uid: "flight@example.com".to_string(), reltype: RelationType::Sibling },
+            RelatedTo { uid: "visa@example.com".to_string(), reltype: RelationType::Other("X-DEPENDS-ON".to_string()) },
+        ]);
+    }
+
+    #[test]
+    fn parses_transparency() {
+        let transp = |line: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\n{line}END:VEVENT\r\nEND:VCALENDAR\r\n")).map(|event| event.transp);
+        assert_eq!(transp(""), Ok(Transparency::Opaque));
+        assert_eq!(transp("TRANSP:TRANSPARENT\r\n"), Ok(Transparency::Transparent));
+        assert!(transp("TRANSP:SEE-THROUGH\r\n").is_err());
+    }
+
+    #[test]
+    fn parses_class() {
+        let class = |line: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\n{line}END:VEVENT\r\nEND:VCALENDAR\r\n")).unwrap().class;
+        assert_eq!(class(""), EventClass::Public);
+        assert_eq!(class("CLASS:CONFIDENTIAL\r\n"), EventClass::Confidential);
+        assert_eq!(class("CLASS:X-INTERNAL\r\n"), EventClass::Other("X-INTERNAL".to_string()));
+    }
+
+    #[test]
+    fn recurring_event_applies_exdates_and_overrides() {
+        let master = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\nRRULE:FREQ=WEEKLY;COUNT=4\r\nEXDATE:20240108T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let moved = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nRECURRENCE-ID:20240115T090000Z\r\nDTSTART:20240116T140000Z\r\nDTEND:20240116T150000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let recurring = RecurringEvent::new(master, vec![moved], &TimezoneMap::new());
+
+        let at = |day, hour| NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let occurrences = recurring.occurrences_between(at(1, 0), at(31, 0)).unwrap();
+        let starts = occurrences.iter().map(|occurrence| occurrence.time.start()).collect::<Vec<_>>();
+        assert_eq!(starts, vec![
+            EventInstant::DateTime(at(1, 9)),
+            EventInstant::DateTime(at(16, 14)),
+            EventInstant::DateTime(at(22, 9)),
+        ]);
+        assert!(occurrences[1].event.recurrence_id.is_some());
+    }
+
+    #[test]
+    fn until_must_match_dtstart_type() {
+        let occurrences = |lines: &str| {
+            let master = event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\n{lines}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).unwrap();
+            let at = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_time(NaiveTime::MIN).and_utc();
+            RecurringEvent::new(master, Vec::new(), &TimezoneMap::new()).occurrences_between(at(1), at(31)).map(|occurrences| occurrences.len())
+        };
+
+        assert_eq!(occurrences("DTSTART;VALUE=DATE:20240101\r\nRRULE:FREQ=DAILY;UNTIL=20240103"), Ok(3));
+        assert_eq!(occurrences("DTSTART:20240101T090000Z\r\nRRULE:FREQ=DAILY;UNTIL=20240103T090000Z"), Ok(3));
+        assert_eq!(occurrences("DTSTART;VALUE=DATE:20240101\r\nRRULE:FREQ=DAILY;UNTIL=20240103T000000Z").err(), Some(Error::UntilMismatch));
+        assert_eq!(occurrences("DTSTART:20240101T090000Z\r\nRRULE:FREQ=DAILY;UNTIL=20240103").err(), Some(Error::UntilMismatch));
+    }
+
+    #[test]
+    fn future_range_override_applies_to_later_instances() {
+        let master = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T100000Z\r\nRRULE:FREQ=WEEKLY;COUNT=5\r\nEXDATE:20240129T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let future = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nUID:weekly\r\nRECURRENCE-ID;RANGE=THISANDFUTURE:20240115T090000Z\r\nDTSTART:20240115T140000Z\r\nDTEND:20240115T160000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let recurring = RecurringEvent::new(master, vec![future], &TimezoneMap::new());
+
+        let at = |day, hour| NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let occurrences = recurring.occurrences_between(at(1, 0), at(31, 0)).unwrap();
+        let times = occurrences.iter().map(|occurrence| occurrence.time.clone()).collect::<Vec<_>>();
+        assert_eq!(times, vec![
+            EventTimeRange::DateTime { start: at(1, 9), end: at(1, 10) },
+            EventTimeRange::DateTime { start: at(8, 9), end: at(8, 10) },
+            EventTimeRange::DateTime { start: at(15, 14), end: at(15, 16) },
+            EventTimeRange::DateTime { start: at(22, 14), end: at(22, 16) },
+        ]);
+        assert!(occurrences[0].event.recurrence_id.is_none());
+        assert!(occurrences[1].event.recurrence_id.is_none());
+        assert!(occurrences[3].event.recurrence_id.is_some());
+    }
+
+    #[test]
+    fn sorts_mixed_variants_by_start_instant() {
+        let mut events = [
+            "DTSTART:20240102T080000Z",
+            "DTSTART;VALUE=DATE:20240102",
+            "DTSTART:20240101T230000",
+        ].map(|start| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\n{start}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).unwrap());
+        events.sort_by_key(|event| event.time.start_instant());
+
+        let starts = events.iter().map(|event| event.time.start()).collect::<Vec<_>>();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        assert_eq!(starts, vec![
+            EventInstant::FloatingDateTime(date(1).and_hms_opt(23, 0, 0).unwrap()),
+            EventInstant::Date(date(2)),
+            EventInstant::DateTime(date(2).and_hms_opt(8, 0, 0).unwrap().and_utc()),
+        ]);
+    }
+
+    #[test]
+    fn week_and_day_duration_needs_lenient_mode() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nDURATION:P1W2D\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::TypeDecode(ICalTypes::Duration)));
+
+        let (event, warnings) = lenient_event(input);
+        assert!(matches!(event.time, EventTimeRange::DateTime { start, end } if end - start == chrono::Duration::days(9)));
+        assert_eq!(warnings, vec![Error::TypeDecode(ICalTypes::Duration)]);
+    }
+
+    #[test]
+    fn all_day_start_with_duration() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let range = |duration: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;VALUE=DATE:20240101\r\nDURATION:{duration}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).unwrap().time;
+        assert_eq!(range("P3D"), EventTimeRange::Date { start: date(1), end: date(4) });
+        assert_eq!(range("P1W"), EventTimeRange::Date { start: date(1), end: date(8) });
+        assert_eq!(range("P1DT1H"), EventTimeRange::Date { start: date(1), end: date(3) });
+    }
+
+    #[test]
+    fn date_start_with_date_time_end_needs_lenient_mode() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART;VALUE=DATE:20240101\r\nDTEND:20240101T120000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::InvalidTimeRange));
+
+        let (event, warnings) = lenient_event(input);
+        let at = |hour| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        assert_eq!(event.time, EventTimeRange::DateTime { start: at(0), end: at(12) });
+        assert_eq!(warnings, vec![Error::PromotedDateStart]);
+
+        let component = IcalParser::new(input.as_bytes()).next().unwrap().unwrap().events.remove(0);
+        let options = ParseOptions { strict: false, default_timezone: Some("Test".to_string()), ..ParseOptions::default() };
+        let event = Event::from_component(component, &test_timezones(), &options, &mut Vec::new()).unwrap();
+        assert_eq!(event.time, EventTimeRange::DateTime { start: at(0) - chrono::Duration::hours(1), end: at(12) });
+    }
+
+    #[test]
+    fn rdate_periods_need_lenient_mode() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000Z\r\nRDATE;VALUE=PERIOD:20240102T090000Z/PT1H\r\nRDATE:20240103T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::UnsupportedRecurrence));
+
+        let (event, warnings) = lenient_event(input);
+        assert_eq!(event.rdates.len(), 1);
+        assert_eq!(warnings, vec![Error::UnsupportedRecurrence]);
+    }
+
+    #[test]
+    fn moved_ranges_stay_in_range() {
+        let end = DateTime::<Utc>::MAX_UTC;
+        let range = EventTimeRange::DateTime { start: end - Duration::hours(1), end };
+        assert_eq!(range.shifted(Duration::hours(1)), None);
+        assert_eq!(range.shifted(-Duration::hours(1)), Some(EventTimeRange::DateTime { start: end - Duration::hours(2), end: end - Duration::hours(1) }));
+        assert_eq!(range.starting_at(EventInstant::DateTime(end)), None);
+        assert_eq!(range.starting_at(EventInstant::FloatingDateTime(NaiveDateTime::MAX)), None);
+    }
+
+    #[test]
+    fn missing_end_uses_default_durations() {
+        let parse = |start: &str, options: &ParseOptions| {
+            let input = format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\n{start}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+            let component = IcalParser::new(input.as_bytes()).next().unwrap().unwrap().events.remove(0);
+            Event::from_component(component, &TimezoneMap::new(), options, &mut Vec::new()).unwrap().time
+        };
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let at = |hour| date(1).and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        let options = ParseOptions::default();
+        assert_eq!(parse("DTSTART;VALUE=DATE:20240101", &options), EventTimeRange::Date { start: date(1), end: date(2) });
+        assert_eq!(parse("DTSTART:20240101T090000Z", &options), EventTimeRange::DateTime { start: at(9), end: at(9) });
+
+        let default_duration = DefaultDurationPolicy { date: chrono::Duration::days(2), date_time: chrono::Duration::hours(1) };
+        let options = ParseOptions { default_duration, ..ParseOptions::default() };
+        assert_eq!(parse("DTSTART;VALUE=DATE:20240101", &options), EventTimeRange::Date { start: date(1), end: date(3) });
+        assert_eq!(parse("DTSTART:20240101T090000Z", &options), EventTimeRange::DateTime { start: at(9), end: at(10) });
+    }
+
+    #[test]
+    fn rejects_end_before_start() {
+        let parse = |timing: &str| event(&format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\n{timing}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")).err();
+        assert_eq!(parse("DTSTART:20240102T090000Z\r\nDTEND:20240101T090000Z"), Some(Error::EndBeforeStart));
+        assert_eq!(parse("DTSTART;VALUE=DATE:20240102\r\nDTEND;VALUE=DATE:20240101"), Some(Error::EndBeforeStart));
+        assert_eq!(parse("DTSTART:20240102T090000\r\nDURATION:-PT1H"), Some(Error::EndBeforeStart));
+        assert_eq!(parse("DTSTART:20240102T090000Z\r\nDTEND:20240102T090000Z"), None);
+    }
+
+    #[test]
+    fn clamps_to_window() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let at = |day, hour| date(day).and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        let timed = EventTimeRange::DateTime { start: at(1, 22), end: at(2, 2) };
+        assert_eq!(timed.clamp(at(2, 0), at(3, 0)), Some(EventTimeRange::DateTime { start: at(2, 0), end: at(2, 2) }));
+        assert_eq!(timed.clamp(at(2, 2), at(3, 0)), None);
+
+        let all_day = EventTimeRange::Date { start: date(1), end: date(8) };
+        assert_eq!(all_day.clamp(at(3, 0), at(5, 12)), Some(EventTimeRange::Date { start: date(3), end: date(6) }));
+        assert_eq!(all_day.clamp(at(8, 0), at(9, 0)), None);
+
+        let instant = EventTimeRange::FloatingDateTime { start: at(1, 9).naive_utc(), end: at(1, 9).naive_utc() };
+        assert_eq!(instant.clamp(at(1, 0), at(2, 0)), Some(instant.clone()));
+    }
+
+    #[test]
+    fn offset_suffix_needs_lenient_mode() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240101T090000+0000\r\nDTEND:20240101T100000+0000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(event(input).err(), Some(Error::TypeDecode(ICalTypes::DateTime)));
+
+        let (event, warnings) = lenient_event(input);
+        let at = |hour| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        assert_eq!(event.time, EventTimeRange::DateTime { start: at(9), end: at(10) });
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn parses_recurrence_id() {
+        let timed = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nRECURRENCE-ID;RANGE=THISANDFUTURE:20240108T090000Z\r\nDTSTART:20240108T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let instant = EventInstant::DateTime(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap().and_hms_opt(9, 0, 0).unwrap().and_utc());
+        assert_eq!(timed.recurrence_id, Some(RecurrenceId { instant, range: RecurrenceRange::ThisAndFuture }));
+
+        let all_day = event("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nRECURRENCE-ID;VALUE=DATE:20240108\r\nDTSTART;VALUE=DATE:20240109\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n").unwrap();
+        let instant = EventInstant::Date(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap());
+        assert_eq!(all_day.recurrence_id, Some(RecurrenceId { instant, range: RecurrenceRange::ThisInstance }));
+    }
+
+    #[test]
+    fn recurrence_id_ranges_ignore_case() {
+        let input = |range: &str| format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTAMP:20240101T000000Z\r\nRECURRENCE-ID;RANGE={range}:20240108T090000Z\r\nDTSTART:20240108T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+        let range = |event: Event| event.recurrence_id.map(|id| id.range);
+        assert_eq!(event(&input("thisAndFuture")).map(range), Ok(Some(RecurrenceRange::ThisAndFuture)));
+
+        assert_eq!(event(&input("THISANDPRIOR")).err(), Some(Error::InvalidRecurrenceId));
+        let (parsed, warnings) = lenient_event(&input("THISANDPRIOR"));
+        assert_eq!(range(parsed), Some(RecurrenceRange::ThisInstance));
+        assert_eq!(warnings, vec![Error::InvalidRecurrenceId]);
+    }
+}
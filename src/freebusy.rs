@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use ical::{parser::ical::component::IcalFreeBusy, property::Property};
+
+use crate::{types::{get_param, IcalDateTime, IcalPeriod}, Error};
+
+pub struct FreeBusy {
+    pub busy: Vec<IcalPeriod>,
+}
+
+fn utc_bounds(period: &IcalPeriod) -> Result<(DateTime<Utc>, DateTime<Utc>), Error> {
+    match period {
+        IcalPeriod::StartEnd { start: IcalDateTime::Utc { date_time: start }, end: IcalDateTime::Utc { date_time: end } } => Ok((*start, *end)),
+        IcalPeriod::StartDuration { start: IcalDateTime::Utc { date_time: start }, duration } => {
+            Ok((*start, start.checked_add_signed(duration.duration).ok_or(Error::InvalidFreeBusy)?))
+        },
+        _ => Err(Error::InvalidFreeBusy),
+    }
+}
+
+impl FreeBusy {
+    /// Merges overlapping and adjacent busy periods into a sorted, minimal set of UTC start/end periods.
+    pub fn merged_busy(&self) -> Result<Vec<IcalPeriod>, Error> {
+        let mut bounds = self.busy.iter().map(utc_bounds).collect::<Result<Vec<_>, _>>()?;
+        bounds.sort();
+
+        let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+        for (start, end) in bounds {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        Ok(merged.into_iter().map(|(start, end)| IcalPeriod::StartEnd { start: IcalDateTime::Utc { date_time: start }, end: IcalDateTime::Utc { date_time: end } }).collect())
+    }
+}
+
+impl TryFrom<IcalFreeBusy> for FreeBusy {
+    type Error = Error;
+
+    fn try_from(value: IcalFreeBusy) -> Result<Self, Self::Error> {
+        let mut busy = Vec::new();
+
+        for prop in value.properties {
+            if prop.name != "FREEBUSY" || get_param(&prop, "FBTYPE").map(|fb_type| fb_type.as_str()) == Some("FREE") {
+                continue;
+            }
+
+            let periods = prop.value.as_deref().ok_or(Error::InvalidFreeBusy)?;
+            for period in periods.split(',') {
+                busy.push(IcalPeriod::try_from(Property { name: prop.name.clone(), value: Some(period.to_string()), params: prop.params.clone() })?);
+            }
+        }
+
+        Ok(FreeBusy { busy })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn free_busy(value: &str) -> FreeBusy {
+        let mut component = IcalFreeBusy::new();
+        component.properties.push(Property { name: "FREEBUSY".to_string(), params: None, value: Some(value.to_string()) });
+        FreeBusy::try_from(component).unwrap()
+    }
+
+    #[test]
+    fn parses_duration_periods() {
+        let free_busy = free_busy("19970308T160000Z/PT8H30M,19970308T230000Z/19970309T000000Z");
+        assert_eq!(free_busy.busy.len(), 2);
+        assert!(matches!(free_busy.busy[0], IcalPeriod::StartDuration { .. }));
+        assert!(matches!(free_busy.busy[1], IcalPeriod::StartEnd { .. }));
+    }
+
+    #[test]
+    fn merges_adjacent_and_overlapping() {
+        let free_busy = free_busy("20240101T090000Z/PT1H,20240101T100000Z/20240101T110000Z,20240101T130000Z/PT2H,20240101T140000Z/20240101T160000Z");
+        let merged = free_busy.merged_busy().unwrap();
+        let expected = [
+            "20240101T090000Z/20240101T110000Z",
+            "20240101T130000Z/20240101T160000Z",
+        ].map(|period| IcalPeriod::try_from(Property { name: "FREEBUSY".to_string(), params: None, value: Some(period.to_string()) }).unwrap());
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn rejects_out_of_range_durations() {
+        assert_eq!(free_busy("20240101T090000Z/P99999999W").merged_busy().err(), Some(Error::InvalidFreeBusy));
+    }
+}
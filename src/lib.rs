@@ -1,8 +1,16 @@
+pub mod alarm;
 pub mod calendar;
 pub mod event;
+pub mod freebusy;
+pub mod parse;
+pub mod participant;
+pub mod recur;
 pub mod timezone;   
 pub mod types;
 
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ICalTypes {
     Binary,
     Boolean,
@@ -20,8 +28,122 @@ pub enum ICalTypes {
     UTCOffset,
 }
 
+impl FromStr for ICalTypes {
+    type Err = Error;
+
+    /// Parameter values are case-insensitive, so `date` names the same type as `DATE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "BINARY" => Ok(ICalTypes::Binary),
+            "BOOLEAN" => Ok(ICalTypes::Boolean),
+            "CAL-ADDRESS" => Ok(ICalTypes::CalAddress),
+            "DATE" => Ok(ICalTypes::Date),
+            "DATE-TIME" => Ok(ICalTypes::DateTime),
+            "DURATION" => Ok(ICalTypes::Duration),
+            "FLOAT" => Ok(ICalTypes::Float),
+            "INTEGER" => Ok(ICalTypes::Integer),
+            "PERIOD" => Ok(ICalTypes::Period),
+            "RECUR" => Ok(ICalTypes::Recur),
+            "TEXT" => Ok(ICalTypes::Text),
+            "TIME" => Ok(ICalTypes::Time),
+            "URI" => Ok(ICalTypes::URI),
+            "UTC-OFFSET" => Ok(ICalTypes::UTCOffset),
+            _ => Err(Error::UnknownValueType(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum Error {
     TypeDecode(ICalTypes),
+    InvalidTimezone,
+    InvalidDate,
+    InvalidDateTime,
+    InvalidTimeRange,
+    InvalidAlarm,
+    InvalidFreeBusy,
+    InvalidCalendar,
+    InvalidEvent,
+    UnknownValueType(String),
+    MissingTimezone(String),
+    /// TZIDs referenced by a calendar's events without a definition, sorted.
+    MissingTimezones(Vec<String>),
+    UnsupportedRecurrence,
+    UnsupportedVersion(String),
+    InvalidRecurrenceId,
+    DuplicateTimezone(String),
+    EndBeforeStart,
+    MissingProperty(String),
+    /// An RRULE's UNTIL is a DATE while DTSTART is a DATE-TIME, or the other way around.
+    UntilMismatch,
+    /// A chrono format string that can't be applied.
+    InvalidFormat,
+    /// A DATE DTSTART paired with a DATE-TIME DTEND, read leniently as midnight in `ParseOptions::default_timezone`.
+    PromotedDateStart,
+    /// Expanding a recurrence examined more instants than `ParseOptions::max_iterations` allows.
+    IterationLimit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingTimezonePolicy {
+    #[default]
+    Reject,
+    Utc,
+    Floating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateTimezonePolicy {
+    #[default]
+    KeepLast,
+    Reject,
+}
+
+/// Durations assumed for events with neither DTEND nor DURATION. RFC 5545 gives all-day events one day and timed
+/// events none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultDurationPolicy {
+    /// Rounded up to whole days.
+    pub date: chrono::Duration,
+    pub date_time: chrono::Duration,
+}
+
+impl Default for DefaultDurationPolicy {
+    fn default() -> Self {
+        DefaultDurationPolicy { date: chrono::Duration::days(1), date_time: chrono::Duration::zero() }
+    }
+}
+
+pub struct ParseOptions {
+    /// When false, recoverable problems are recorded as warnings and the offending value is skipped.
+    pub strict: bool,
+    /// How to treat a TZID with no matching VTIMEZONE. Any fallback is recorded as a warning.
+    pub missing_timezone: MissingTimezonePolicy,
+    /// What to do when several VTIMEZONE components share a TZID.
+    pub duplicate_timezone: DuplicateTimezonePolicy,
+    pub default_duration: DefaultDurationPolicy,
+    /// Cap on the periods and candidate instants examined when expanding a recurrence.
+    pub max_iterations: usize,
+    /// TZID whose midnight a DATE DTSTART becomes when paired with a DATE-TIME DTEND in lenient mode. UTC when
+    /// unset; a floating DTEND keeps the start floating.
+    pub default_timezone: Option<String>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { strict: true, missing_timezone: MissingTimezonePolicy::Reject, duplicate_timezone: DuplicateTimezonePolicy::KeepLast, default_duration: DefaultDurationPolicy::default(), max_iterations: recur::DEFAULT_MAX_ITERATIONS, default_timezone: None }
+    }
+}
+
+impl ParseOptions {
+    pub(crate) fn recover(&self, error: Error, warnings: &mut Vec<Error>) -> Result<(), Error> {
+        if self.strict {
+            Err(error)
+        } else {
+            warnings.push(error);
+            Ok(())
+        }
+    }
 }
 
 pub fn add(left: usize, right: usize) -> usize {
@@ -37,4 +159,11 @@ mod tests {
         let result = add(2, 2);
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn value_types_ignore_case() {
+        assert_eq!("date".parse::<ICalTypes>(), Ok(ICalTypes::Date));
+        assert_eq!("Date-Time".parse::<ICalTypes>(), Ok(ICalTypes::DateTime));
+        assert_eq!("dates".parse::<ICalTypes>(), Err(Error::UnknownValueType("dates".to_string())));
+    }
 }
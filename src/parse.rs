@@ -0,0 +1,59 @@
+//! Parsers for bare iCalendar values, independent of any property or its parameters. Each function accepts exactly
+//! the RFC 5545 syntax for its type and reports failure as `Error::TypeDecode`, so callers don't depend on the
+//! underlying grammar's error type.
+
+use chrono::NaiveDate;
+
+use crate::{types::{ical_type_parser, ICalDuration, ICalTime, IcalDate, IcalDateTime, IcalPeriod, IcalRecur, IcalUTCOffset}, Error, ICalTypes};
+
+/// A DATE such as `20240131`.
+pub fn date(value: &str) -> Result<NaiveDate, Error> {
+    value.parse::<IcalDate>().map(|date| date.date)
+}
+
+/// A TIME such as `090000` or `090000Z`. Never `ICalTime::Local`, since a TZID only comes from a parameter.
+pub fn time(value: &str) -> Result<ICalTime, Error> {
+    ical_type_parser::time(value).map_err(|_| Error::TypeDecode(ICalTypes::Time))
+}
+
+/// A DATE-TIME such as `20240131T090000Z`, either UTC or floating.
+pub fn date_time(value: &str) -> Result<IcalDateTime, Error> {
+    value.parse()
+}
+
+/// A DURATION such as `P1DT2H` or `-PT15M`.
+pub fn duration(value: &str) -> Result<ICalDuration, Error> {
+    ical_type_parser::duration(value).map_err(|_| Error::TypeDecode(ICalTypes::Duration))
+}
+
+/// A PERIOD given as `start/end` or `start/duration`.
+pub fn period(value: &str) -> Result<IcalPeriod, Error> {
+    ical_type_parser::period(value).map_err(|_| Error::TypeDecode(ICalTypes::Period))
+}
+
+/// A RECUR value such as `FREQ=WEEKLY;BYDAY=MO,WE`, normalized. Range checks are left to `IcalRecur::validate`.
+pub fn recur(value: &str) -> Result<IcalRecur, Error> {
+    value.parse()
+}
+
+/// A UTC-OFFSET such as `-0500` or `+053045`.
+pub fn utc_offset(value: &str) -> Result<IcalUTCOffset, Error> {
+    ical_type_parser::utc_offset(value).map_err(|_| Error::TypeDecode(ICalTypes::UTCOffset))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn reports_failures_as_type_decode() {
+        assert_eq!(date("20240131"), Ok(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()));
+        assert_eq!(date("20240132"), Err(Error::TypeDecode(ICalTypes::Date)));
+        assert!(matches!(time("090000Z"), Ok(ICalTime::Utc { .. })));
+        assert_eq!(duration("PT15M").map(|duration| duration.duration), Ok(Duration::minutes(15)));
+        assert_eq!(recur("FREQ=SOMETIMES").err(), Some(Error::TypeDecode(ICalTypes::Recur)));
+        assert_eq!(utc_offset("+05").err(), Some(Error::TypeDecode(ICalTypes::UTCOffset)));
+    }
+}
@@ -0,0 +1,222 @@
+use std::str::FromStr;
+
+use http::Uri;
+use ical::property::Property;
+
+use crate::{types::{get_param, ical_type_parser, new_property, ICalCalAddress}, Error, ICalTypes};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ParticipationStatus {
+    #[default]
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+    Delegated,
+    Other(String),
+}
+
+impl From<String> for ParticipationStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "NEEDS-ACTION" => ParticipationStatus::NeedsAction,
+            "ACCEPTED" => ParticipationStatus::Accepted,
+            "DECLINED" => ParticipationStatus::Declined,
+            "TENTATIVE" => ParticipationStatus::Tentative,
+            "DELEGATED" => ParticipationStatus::Delegated,
+            _ => ParticipationStatus::Other(value),
+        }
+    }
+}
+
+impl ParticipationStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ParticipationStatus::NeedsAction => "NEEDS-ACTION",
+            ParticipationStatus::Accepted => "ACCEPTED",
+            ParticipationStatus::Declined => "DECLINED",
+            ParticipationStatus::Tentative => "TENTATIVE",
+            ParticipationStatus::Delegated => "DELEGATED",
+            ParticipationStatus::Other(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ParticipantRole {
+    Chair,
+    #[default]
+    RequiredParticipant,
+    OptionalParticipant,
+    NonParticipant,
+    Other(String),
+}
+
+impl From<String> for ParticipantRole {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "CHAIR" => ParticipantRole::Chair,
+            "REQ-PARTICIPANT" => ParticipantRole::RequiredParticipant,
+            "OPT-PARTICIPANT" => ParticipantRole::OptionalParticipant,
+            "NON-PARTICIPANT" => ParticipantRole::NonParticipant,
+            _ => ParticipantRole::Other(value),
+        }
+    }
+}
+
+impl ParticipantRole {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ParticipantRole::Chair => "CHAIR",
+            ParticipantRole::RequiredParticipant => "REQ-PARTICIPANT",
+            ParticipantRole::OptionalParticipant => "OPT-PARTICIPANT",
+            ParticipantRole::NonParticipant => "NON-PARTICIPANT",
+            ParticipantRole::Other(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CalendarUserType {
+    #[default]
+    Individual,
+    Group,
+    Resource,
+    Room,
+    Unknown,
+    Other(String),
+}
+
+impl From<String> for CalendarUserType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "INDIVIDUAL" => CalendarUserType::Individual,
+            "GROUP" => CalendarUserType::Group,
+            "RESOURCE" => CalendarUserType::Resource,
+            "ROOM" => CalendarUserType::Room,
+            "UNKNOWN" => CalendarUserType::Unknown,
+            _ => CalendarUserType::Other(value),
+        }
+    }
+}
+
+impl CalendarUserType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CalendarUserType::Individual => "INDIVIDUAL",
+            CalendarUserType::Group => "GROUP",
+            CalendarUserType::Resource => "RESOURCE",
+            CalendarUserType::Room => "ROOM",
+            CalendarUserType::Unknown => "UNKNOWN",
+            CalendarUserType::Other(value) => value,
+        }
+    }
+}
+
+/// An ATTENDEE. Absent parameters take their RFC 5545 defaults: a required individual who hasn't answered and
+/// isn't asked to.
+pub struct Attendee {
+    pub address: ICalCalAddress,
+    pub common_name: Option<String>,
+    pub role: ParticipantRole,
+    pub part_stat: ParticipationStatus,
+    pub cu_type: CalendarUserType,
+    pub rsvp: bool,
+}
+
+impl Attendee {
+    /// The ATTENDEE property, leaving out parameters that hold their defaults.
+    pub fn to_property(&self) -> Property {
+        let mut params = Vec::new();
+        if let Some(common_name) = &self.common_name {
+            params.push(("CN", common_name.as_str()));
+        }
+        if self.role != ParticipantRole::default() {
+            params.push(("ROLE", self.role.as_str()));
+        }
+        if self.part_stat != ParticipationStatus::default() {
+            params.push(("PARTSTAT", self.part_stat.as_str()));
+        }
+        if self.cu_type != CalendarUserType::default() {
+            params.push(("CUTYPE", self.cu_type.as_str()));
+        }
+        if self.rsvp {
+            params.push(("RSVP", "TRUE"));
+        }
+        new_property("ATTENDEE", &params, self.address.address.to_string())
+    }
+}
+
+impl TryFrom<Property> for Attendee {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<Self, Self::Error> {
+        let common_name = get_param(&property, "CN").cloned();
+        let role = get_param(&property, "ROLE").cloned().map(ParticipantRole::from).unwrap_or_default();
+        let part_stat = get_param(&property, "PARTSTAT").cloned().map(ParticipationStatus::from).unwrap_or_default();
+        let cu_type = get_param(&property, "CUTYPE").cloned().map(CalendarUserType::from).unwrap_or_default();
+        let rsvp = get_param(&property, "RSVP").map(|rsvp| ical_type_parser::boolean(rsvp).map_err(|_| Error::TypeDecode(ICalTypes::Boolean))).transpose()?.unwrap_or(false);
+
+        Ok(Attendee { address: ICalCalAddress::try_from(property)?, common_name, role, part_stat, cu_type, rsvp })
+    }
+}
+
+pub struct Organizer {
+    pub address: ICalCalAddress,
+    pub common_name: Option<String>,
+    pub sent_by: Option<Uri>,
+}
+
+impl Organizer {
+    pub fn to_property(&self) -> Property {
+        let sent_by = self.sent_by.as_ref().map(Uri::to_string);
+        let mut params = Vec::new();
+        if let Some(common_name) = &self.common_name {
+            params.push(("CN", common_name.as_str()));
+        }
+        if let Some(sent_by) = &sent_by {
+            params.push(("SENT-BY", sent_by.as_str()));
+        }
+        new_property("ORGANIZER", &params, self.address.address.to_string())
+    }
+}
+
+impl TryFrom<Property> for Organizer {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<Self, Self::Error> {
+        let common_name = get_param(&property, "CN").cloned();
+        let sent_by = get_param(&property, "SENT-BY").map(|sent_by| Uri::from_str(sent_by).map_err(|_| Error::TypeDecode(ICalTypes::CalAddress))).transpose()?;
+
+        Ok(Organizer { address: ICalCalAddress::try_from(property)?, common_name, sent_by })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attendee(params: &[(&str, &str)]) -> Result<Attendee, Error> {
+        let params = params.iter().map(|(name, value)| (name.to_string(), vec![value.to_string()])).collect();
+        Attendee::try_from(Property { name: "ATTENDEE".to_string(), params: Some(params), value: Some("mailto:jane@example.com".to_string()) })
+    }
+
+    #[test]
+    fn parses_participation_parameters() {
+        let parsed = attendee(&[("ROLE", "CHAIR"), ("PARTSTAT", "ACCEPTED"), ("CUTYPE", "ROOM"), ("RSVP", "TRUE")]).unwrap();
+        assert_eq!(parsed.role, ParticipantRole::Chair);
+        assert_eq!(parsed.part_stat, ParticipationStatus::Accepted);
+        assert_eq!(parsed.cu_type, CalendarUserType::Room);
+        assert!(parsed.rsvp);
+
+        let defaults = attendee(&[]).unwrap();
+        assert_eq!(defaults.role, ParticipantRole::RequiredParticipant);
+        assert_eq!(defaults.part_stat, ParticipationStatus::NeedsAction);
+        assert_eq!(defaults.cu_type, CalendarUserType::Individual);
+        assert!(!defaults.rsvp);
+
+        let unknown = attendee(&[("ROLE", "X-OBSERVER"), ("PARTSTAT", "IN-PROCESS")]).unwrap();
+        assert_eq!(unknown.role, ParticipantRole::Other("X-OBSERVER".to_string()));
+        assert_eq!(unknown.part_stat, ParticipationStatus::Other("IN-PROCESS".to_string()));
+
+        assert!(matches!(attendee(&[("RSVP", "YES")]), Err(Error::TypeDecode(ICalTypes::Boolean))));
+    }
+}
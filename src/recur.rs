@@ -0,0 +1,979 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, VecDeque}, iter::{FusedIterator, Peekable}};
+
+use chrono::{DateTime, Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc, Weekday};
+
+use crate::{timezone::Timezone, types::{ICalRecurDayOfWeek, ICalRecurFrequency, IcalRecur, IcalRecurLimit, IcalRecurUntil}, Error, ParseOptions};
+
+/// Default cap on the periods and candidate instants an expansion may examine.
+pub const DEFAULT_MAX_ITERATIONS: usize = 100_000;
+
+fn weekday(day: ICalRecurDayOfWeek) -> Weekday {
+    match day {
+        ICalRecurDayOfWeek::Sunday => Weekday::Sun,
+        ICalRecurDayOfWeek::Monday => Weekday::Mon,
+        ICalRecurDayOfWeek::Tuesday => Weekday::Tue,
+        ICalRecurDayOfWeek::Wednesday => Weekday::Wed,
+        ICalRecurDayOfWeek::Thursday => Weekday::Thu,
+        ICalRecurDayOfWeek::Friday => Weekday::Fri,
+        ICalRecurDayOfWeek::Saturday => Weekday::Sat,
+    }
+}
+
+/// First day of the week containing `date`. WEEKLY periods start here, so INTERVAL counts whole WKST-aligned weeks.
+fn week_start(date: NaiveDate, wkst: Weekday) -> NaiveDate {
+    let offset = (date.weekday().num_days_from_monday() + 7 - wkst.num_days_from_monday()) % 7;
+    date - Duration::days(offset.into())
+}
+
+fn month_dates(year: i32, month: u32) -> Vec<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, 1).map(|first| first.iter_days().take_while(|date| date.month() == month).collect()).unwrap_or_default()
+}
+
+fn month_len(date: NaiveDate) -> u32 {
+    let first = date - Duration::days(date.day0().into());
+    first.checked_add_months(Months::new(1)).map_or(31, |next| (next - first).num_days() as u32)
+}
+
+fn year_len(date: NaiveDate) -> u32 {
+    if date.leap_year() { 366 } else { 365 }
+}
+
+fn year_dates(year: i32) -> Vec<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, 1, 1).map(|first| first.iter_days().take_while(|date| date.year() == year).collect()).unwrap_or_default()
+}
+
+/// Start of week 1 of `year`: the first WKST-aligned week with at least four days in the year, which is the one
+/// containing January 4th.
+fn week_one(year: i32, wkst: Weekday) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, 1, 4).map(|date| week_start(date, wkst))
+}
+
+/// Every date of the listed weeks of `year`, counted from the start (positive) or the end (negative) of its 52 or 53
+/// weeks. Weeks past the year's last are skipped, and the first and last weeks may spill into the adjacent years.
+fn week_no_dates(year: i32, week_nos: &[i32], wkst: Weekday) -> Vec<NaiveDate> {
+    let (Some(first), Some(next)) = (week_one(year, wkst), year.checked_add(1).and_then(|next| week_one(next, wkst))) else {
+        return Vec::new();
+    };
+    let weeks = (next - first).num_weeks();
+
+    week_nos.iter()
+        .filter_map(|week| {
+            let index = if *week > 0 { i64::from(*week) - 1 } else { weeks + i64::from(*week) };
+            (0..weeks).contains(&index).then(|| first + Duration::weeks(index))
+        })
+        .flat_map(|start| start.iter_days().take(7))
+        .collect()
+}
+
+/// Whether day `index` of a `len` day scope is the `position`th one, counting every `stride` days from the start
+/// (positive) or the end (negative) of the scope.
+fn matches_position(position: i32, index: u32, len: u32, stride: u32) -> bool {
+    let from_start = i64::from(index / stride);
+    let from_end = i64::from((len - 1 - index) / stride);
+    if position > 0 {
+        from_start == i64::from(position) - 1
+    } else {
+        from_end == -i64::from(position) - 1
+    }
+}
+
+/// The period of `frequency` holding `time`, numbered from the one holding `dtstart`. Sub-daily periods are aligned
+/// to DTSTART's minutes and seconds, so a time before that point in its hour or minute counts as the next period.
+fn period_number(frequency: ICalRecurFrequency, wkst: Weekday, dtstart: NaiveDateTime, time: NaiveDateTime) -> i64 {
+    match frequency {
+        ICalRecurFrequency::Yearly => i64::from(time.year() - dtstart.year()),
+        ICalRecurFrequency::Monthly => i64::from(time.year() - dtstart.year()) * 12 + i64::from(time.month()) - i64::from(dtstart.month()),
+        ICalRecurFrequency::Weekly => (week_start(time.date(), wkst) - week_start(dtstart.date(), wkst)).num_weeks(),
+        ICalRecurFrequency::Daily => (time.date() - dtstart.date()).num_days(),
+        ICalRecurFrequency::Hourly => (time - dtstart).num_hours() + i64::from(time.minute() * 60 + time.second() < dtstart.minute() * 60 + dtstart.second()),
+        ICalRecurFrequency::Minutely => (time - dtstart).num_minutes() + i64::from(time.second() < dtstart.second()),
+        ICalRecurFrequency::Secondly => (time - dtstart).num_seconds(),
+    }
+}
+
+pub struct Occurrences<'a> {
+    recur: &'a IcalRecur,
+    dtstart: NaiveDateTime,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    wkst: Weekday,
+    months: Option<Vec<u32>>,
+    week_nos: Option<Vec<i32>>,
+    year_days: Option<Vec<i32>>,
+    month_days: Option<Vec<i32>>,
+    week_days: Option<Vec<(Weekday, Option<i32>)>>,
+    period: u64,
+    /// A UTC UNTIL as a bound on the times being expanded. Zoned expansion widens it to cover local times.
+    until: Option<NaiveDateTime>,
+    pending: VecDeque<NaiveDateTime>,
+    generated: u64,
+    iterations: usize,
+    max_iterations: usize,
+    limit_reached: bool,
+    finished: bool,
+}
+
+impl IcalRecur {
+    /// Expands the rule from `dtstart`, yielding only the occurrences within `[start, end]`.
+    ///
+    /// `dtstart` is always the first occurrence, even when it does not match the BY* rules, and
+    /// every later occurrence is one the rules produce. COUNT includes `dtstart` and is counted
+    /// from it regardless of the window, and iteration stops once the window has passed. Without
+    /// a COUNT, expansion starts just before the window, so the iteration cap only covers it.
+    pub fn occurrences_between(&self, dtstart: NaiveDateTime, start: NaiveDateTime, end: NaiveDateTime) -> Result<Occurrences<'_>, Error> {
+        // BYWEEKNO is only defined for YEARLY rules, and BYYEARDAY is not defined for DAILY, WEEKLY or MONTHLY ones.
+        let year_day_frequency = !matches!(self.frequency, ICalRecurFrequency::Daily | ICalRecurFrequency::Weekly | ICalRecurFrequency::Monthly);
+        if (self.by_week_no.is_some() && self.frequency != ICalRecurFrequency::Yearly) || (self.by_year_day.is_some() && !year_day_frequency) {
+            return Err(Error::UnsupportedRecurrence);
+        }
+
+        let no_day_rules = self.by_year_day.is_none() && self.by_month_day.is_none() && self.by_day.is_none();
+
+        let mut months = self.by_month.as_ref().map(|months| months.iter().map(|month| u32::from(*month)).collect());
+        let mut month_days = self.by_month_day.as_ref().map(|days| days.iter().map(|day| i32::from(*day)).collect());
+        let mut week_days = self.by_day.as_ref().map(|days| days.iter().map(|day| (weekday(day.day), day.nth_of_month.map(i32::from))).collect());
+
+        if no_day_rules && self.by_week_no.is_some() {
+            week_days = Some(vec![(dtstart.weekday(), None)]);
+        } else if no_day_rules {
+            match self.frequency {
+                ICalRecurFrequency::Yearly => {
+                    months.get_or_insert_with(|| vec![dtstart.month()]);
+                    month_days = Some(vec![dtstart.day() as i32]);
+                },
+                ICalRecurFrequency::Monthly => month_days = Some(vec![dtstart.day() as i32]),
+                ICalRecurFrequency::Weekly => week_days = Some(vec![(dtstart.weekday(), None)]),
+                _ => {}
+            }
+        }
+
+        // Periods before the window can't produce anything in it, so unless COUNT needs them counted, expansion skips
+        // to the period before the one holding `start`.
+        let wkst = self.wkst.map(weekday).unwrap_or(Weekday::Mon);
+        let interval = i64::try_from(self.interval.unwrap_or(1).max(1)).unwrap_or(i64::MAX);
+        let period = match self.limit {
+            Some(IcalRecurLimit::Count(_)) => 0,
+            _ if start <= dtstart => 0,
+            _ => u64::try_from((period_number(self.frequency, wkst, dtstart, start) - 1).div_euclid(interval)).unwrap_or(0),
+        };
+
+        Ok(Occurrences {
+            recur: self,
+            dtstart,
+            start,
+            end,
+            wkst,
+            months,
+            week_nos: self.by_week_no.as_ref().map(|weeks| weeks.iter().map(|week| i32::from(*week)).collect()),
+            year_days: self.by_year_day.as_ref().map(|days| days.iter().map(|day| i32::from(*day)).collect()),
+            month_days,
+            week_days,
+            period,
+            until: self.utc_until().map(|until| until.naive_utc()),
+            pending: VecDeque::from([dtstart]),
+            generated: 0,
+            iterations: 0,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            limit_reached: false,
+            finished: false,
+        })
+    }
+
+    /// Expands the rule in `timezone`'s local time, so occurrences keep their wall-clock time across DST changes,
+    /// and yields the UTC instants within `[start, end]`. `dtstart` is the local DTSTART.
+    pub fn occurrences_between_in<'a>(&'a self, dtstart: NaiveDateTime, timezone: &'a Timezone, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<ZonedOccurrences<'a>, Error> {
+        if timezone.transitions().is_empty() {
+            return Err(Error::InvalidTimezone);
+        }
+
+        // Local wall-clock times are always within a day of UTC, so a wider local window covers any offset.
+        let slack = Duration::days(2);
+        let local_start = start.naive_utc().checked_sub_signed(slack).unwrap_or(NaiveDateTime::MIN);
+        let local_end = end.naive_utc().checked_add_signed(slack).unwrap_or(NaiveDateTime::MAX);
+        let mut occurrences = self.occurrences_between(dtstart, local_start, local_end)?;
+        let max_offset = timezone.transitions().iter().flat_map(|transition| [transition.offset, transition.offset_from]).max().unwrap_or_else(Duration::zero);
+
+        // No local time before UNTIL is more than `max_offset` past it, so expansion stops there and each converted
+        // instant is checked against UNTIL itself.
+        occurrences = occurrences.until_slack(max_offset);
+        let until = self.utc_until();
+
+        Ok(ZonedOccurrences { occurrences, timezone, start, end, until, max_offset, latest_local: None, pending: BinaryHeap::new(), last: None })
+    }
+
+    /// Like `occurrences_between_in`, but pairs each UTC instant with its local wall-clock time and the zone's
+    /// TZNAME abbreviation at that instant.
+    pub fn occurrences_local<'a>(&'a self, dtstart: NaiveDateTime, timezone: &'a Timezone, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<LocalOccurrences<'a>, Error> {
+        Ok(self.occurrences_between_in(dtstart, timezone, start, end)?.local())
+    }
+
+    /// UNTIL when it's a UTC DATE-TIME.
+    pub(crate) fn utc_until(&self) -> Option<DateTime<Utc>> {
+        match self.limit {
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(until))) => Some(until),
+            _ => None,
+        }
+    }
+
+    /// A quick upper bound on the occurrences within `[start, end]`, from the number of periods the window spans and
+    /// the most instances the BY* rules can produce in one period, without expanding anything. Rules with a COUNT
+    /// just return it.
+    pub fn estimated_count(&self, dtstart: NaiveDateTime, start: NaiveDateTime, end: NaiveDateTime) -> usize {
+        if let Some(IcalRecurLimit::Count(count)) = self.limit {
+            return usize::try_from(count).unwrap_or(usize::MAX);
+        }
+
+        let until = match self.limit {
+            Some(IcalRecurLimit::Until(IcalRecurUntil::Date(until))) => until.and_hms_opt(23, 59, 59),
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(until))) => Some(until.naive_utc()),
+            _ => None,
+        };
+        let (first, last) = (start.max(dtstart), until.map_or(end, |until| end.min(until)));
+        if last < first {
+            return 0;
+        }
+
+        // Periods are numbered from the one holding DTSTART, and only every INTERVAL-th produces occurrences.
+        let wkst = self.wkst.map(weekday).unwrap_or(Weekday::Mon);
+        let period = |time: NaiveDateTime| period_number(self.frequency, wkst, dtstart, time);
+        let interval = i64::try_from(self.interval.unwrap_or(1).max(1)).unwrap_or(i64::MAX);
+        let periods = (period(last).div_euclid(interval) - (period(first) + interval - 1).div_euclid(interval) + 1).max(0) as u64;
+
+        let list = |values: Option<usize>| values.map_or(1, |len| len as u64);
+        let by_day = |per_scope: u64| self.by_day.as_ref().map(|days| days.iter().map(|day| if day.nth_of_month.is_some() { 1 } else { per_scope }).sum::<u64>());
+        let month_days = self.by_month_day.as_ref().map(|days| days.len() as u64);
+        let days = match self.frequency {
+            ICalRecurFrequency::Yearly => match (&self.by_year_day, &self.by_week_no, &self.by_month) {
+                (Some(year_days), _, _) => year_days.len() as u64,
+                (None, Some(weeks), _) => weeks.len() as u64 * list(self.by_day.as_ref().map(Vec::len)),
+                (None, None, months) => {
+                    let months = months.as_ref().map(|months| months.len() as u64);
+                    match (month_days, months) {
+                        (Some(month_days), months) => month_days.min(by_day(5).unwrap_or(u64::MAX)) * months.unwrap_or(12),
+                        (None, Some(months)) => months * by_day(5).unwrap_or(1),
+                        (None, None) => by_day(53).unwrap_or(1),
+                    }
+                },
+            },
+            ICalRecurFrequency::Monthly => month_days.map_or(by_day(5).unwrap_or(1), |month_days| month_days.min(by_day(5).unwrap_or(u64::MAX))),
+            ICalRecurFrequency::Weekly => list(self.by_day.as_ref().map(Vec::len)),
+            _ => 1,
+        };
+
+        // BYHOUR, BYMINUTE and BYSECOND add instances within a day only when they are finer than FREQ.
+        let coarser_than = |frequencies: &[ICalRecurFrequency]| !frequencies.contains(&self.frequency);
+        let hours = if coarser_than(&[ICalRecurFrequency::Hourly, ICalRecurFrequency::Minutely, ICalRecurFrequency::Secondly]) { list(self.by_hour.as_ref().map(Vec::len)) } else { 1 };
+        let minutes = if coarser_than(&[ICalRecurFrequency::Minutely, ICalRecurFrequency::Secondly]) { list(self.by_minute.as_ref().map(Vec::len)) } else { 1 };
+        let seconds = if coarser_than(&[ICalRecurFrequency::Secondly]) { list(self.by_second.as_ref().map(Vec::len)) } else { 1 };
+
+        let mut per_period = days.saturating_mul(hours).saturating_mul(minutes).saturating_mul(seconds);
+        if let Some(positions) = &self.by_set_pos {
+            per_period = per_period.min(positions.len() as u64);
+        }
+
+        // DTSTART counts even when the BY* rules don't produce it.
+        let by_rules = [self.by_second.is_some(), self.by_minute.is_some(), self.by_hour.is_some(), self.by_day.is_some(), self.by_month_day.is_some(), self.by_year_day.is_some(), self.by_week_no.is_some(), self.by_month.is_some()];
+        let dtstart_extra = u64::from(first == dtstart && by_rules.contains(&true));
+        usize::try_from(periods.saturating_mul(per_period).saturating_add(dtstart_extra)).unwrap_or(usize::MAX)
+    }
+}
+
+impl<'a> Occurrences<'a> {
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn with_options(self, options: &ParseOptions) -> Self {
+        self.max_iterations(options.max_iterations)
+    }
+
+    /// Lets local times up to `slack` past a UTC UNTIL through, for callers that convert each occurrence to UTC and
+    /// check UNTIL themselves.
+    pub(crate) fn until_slack(mut self, slack: Duration) -> Self {
+        self.until = self.until.map(|until| until.checked_add_signed(slack).unwrap_or(NaiveDateTime::MAX));
+        self
+    }
+
+    /// Whether expansion was cut short by the iteration cap rather than by the rule or the window.
+    pub fn limit_reached(&self) -> bool {
+        self.limit_reached
+    }
+
+    fn period_start(&self) -> Option<NaiveDateTime> {
+        let step = self.period.checked_mul(self.recur.interval.unwrap_or(1).max(1))?;
+        let date = self.dtstart.date();
+
+        match self.recur.frequency {
+            ICalRecurFrequency::Yearly => NaiveDate::from_ymd_opt(date.year().checked_add(i32::try_from(step).ok()?)?, 1, 1).map(|date| date.and_time(NaiveTime::MIN)),
+            ICalRecurFrequency::Monthly => date.with_day(1)?.checked_add_months(Months::new(u32::try_from(step).ok()?)).map(|date| date.and_time(NaiveTime::MIN)),
+            ICalRecurFrequency::Weekly => week_start(date, self.wkst).checked_add_days(Days::new(step.checked_mul(7)?)).map(|date| date.and_time(NaiveTime::MIN)),
+            ICalRecurFrequency::Daily => date.checked_add_days(Days::new(step)).map(|date| date.and_time(NaiveTime::MIN)),
+            ICalRecurFrequency::Hourly => {
+                let hour = date.and_hms_opt(self.dtstart.hour(), 0, 0)?;
+                hour.checked_add_signed(Duration::try_hours(i64::try_from(step).ok()?)?)
+            },
+            ICalRecurFrequency::Minutely => {
+                let minute = date.and_hms_opt(self.dtstart.hour(), self.dtstart.minute(), 0)?;
+                minute.checked_add_signed(Duration::try_minutes(i64::try_from(step).ok()?)?)
+            },
+            ICalRecurFrequency::Secondly => {
+                let second = self.dtstart.with_nanosecond(0)?;
+                second.checked_add_signed(Duration::try_seconds(i64::try_from(step).ok()?)?)
+            },
+        }
+    }
+
+    fn matches_week_day(&self, date: NaiveDate, day: Weekday, nth: Option<i32>) -> bool {
+        if date.weekday() != day {
+            return false;
+        }
+
+        match (nth, self.recur.frequency) {
+            (Some(nth), ICalRecurFrequency::Monthly) => matches_position(nth, date.day0(), month_len(date), 7),
+            (Some(nth), ICalRecurFrequency::Yearly) if self.recur.by_month.is_some() => matches_position(nth, date.day0(), month_len(date), 7),
+            (Some(nth), ICalRecurFrequency::Yearly) => matches_position(nth, date.ordinal0(), year_len(date), 7),
+            _ => true,
+        }
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if let Some(months) = &self.months {
+            if !months.contains(&date.month()) {
+                return false;
+            }
+        }
+
+        // Negative days count back from December 31st, so -1 is day 365 or, in leap years, 366.
+        if let Some(year_days) = &self.year_days {
+            if !year_days.iter().any(|day| matches_position(*day, date.ordinal0(), year_len(date), 1)) {
+                return false;
+            }
+        }
+
+        if let Some(month_days) = &self.month_days {
+            if !month_days.iter().any(|day| matches_position(*day, date.day0(), month_len(date), 1)) {
+                return false;
+            }
+        }
+
+        if let Some(week_days) = &self.week_days {
+            if !week_days.iter().any(|(day, nth)| self.matches_week_day(date, *day, *nth)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn times(&self, period_start: NaiveDateTime) -> Vec<NaiveTime> {
+        let select = |fixed: bool, current: u32, by: &Option<Vec<u8>>, default: u32| -> Vec<u32> {
+            match (fixed, by) {
+                (true, Some(by)) => by.iter().map(|value| u32::from(*value)).filter(|value| *value == current).collect(),
+                (true, None) => vec![current],
+                (false, Some(by)) => by.iter().map(|value| u32::from(*value)).collect(),
+                (false, None) => vec![default],
+            }
+        };
+
+        let (fixed_hour, fixed_minute, fixed_second) = match self.recur.frequency {
+            ICalRecurFrequency::Hourly => (true, false, false),
+            ICalRecurFrequency::Minutely => (true, true, false),
+            ICalRecurFrequency::Secondly => (true, true, true),
+            _ => (false, false, false),
+        };
+
+        let hours = select(fixed_hour, period_start.hour(), &self.recur.by_hour, self.dtstart.hour());
+        let minutes = select(fixed_minute, period_start.minute(), &self.recur.by_minute, self.dtstart.minute());
+        let seconds = select(fixed_second, period_start.second(), &self.recur.by_second, self.dtstart.second());
+
+        let mut times = Vec::new();
+        for hour in &hours {
+            for minute in &minutes {
+                times.extend(seconds.iter().filter_map(|second| NaiveTime::from_hms_opt(*hour, *minute, *second)));
+            }
+        }
+        times.sort();
+        times.dedup();
+        times
+    }
+
+    fn candidates(&self, period_start: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let dates = match self.recur.frequency {
+            ICalRecurFrequency::Yearly => match &self.week_nos {
+                Some(week_nos) => week_no_dates(period_start.year(), week_nos, self.wkst),
+                None => year_dates(period_start.year()),
+            },
+            ICalRecurFrequency::Monthly => month_dates(period_start.year(), period_start.month()),
+            ICalRecurFrequency::Weekly => period_start.date().iter_days().take(7).collect(),
+            _ => vec![period_start.date()],
+        };
+
+        let times = self.times(period_start);
+        let mut candidates = dates.into_iter()
+            .filter(|date| self.matches_date(*date))
+            .flat_map(|date| times.iter().map(move |time| date.and_time(*time)))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+
+        match &self.recur.by_set_pos {
+            Some(positions) => {
+                let len = candidates.len() as i64;
+                let mut selected = positions.iter()
+                    .filter_map(|position| {
+                        let index = if *position > 0 { i64::from(*position) - 1 } else { len + i64::from(*position) };
+                        usize::try_from(index).ok().and_then(|index| candidates.get(index).copied())
+                    })
+                    .collect::<Vec<_>>();
+                selected.sort();
+                selected.dedup();
+                selected
+            },
+            None => candidates,
+        }
+    }
+
+    fn past_limit(&self, occurrence: NaiveDateTime) -> bool {
+        match &self.recur.limit {
+            Some(IcalRecurLimit::Count(count)) => self.generated >= *count,
+            Some(IcalRecurLimit::Until(IcalRecurUntil::Date(until))) => occurrence.date() > *until,
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(_))) => self.until.is_some_and(|until| occurrence > until),
+            None => false,
+        }
+    }
+}
+
+impl<'a> Iterator for Occurrences<'a> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(occurrence) = self.pending.pop_front() {
+                if self.past_limit(occurrence) || occurrence > self.end {
+                    self.finished = true;
+                    self.pending.clear();
+                    return None;
+                }
+
+                self.generated += 1;
+                if occurrence >= self.start {
+                    return Some(occurrence);
+                }
+                continue;
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            if self.iterations >= self.max_iterations {
+                self.limit_reached = true;
+                self.finished = true;
+                return None;
+            }
+
+            match self.period_start() {
+                Some(period_start) if period_start <= self.end => {
+                    self.period += 1;
+                    let candidates = self.candidates(period_start);
+                    self.iterations = self.iterations.saturating_add(candidates.len() + 1);
+                    let dtstart = self.dtstart;
+                    self.pending.extend(candidates.into_iter().filter(|candidate| *candidate > dtstart));
+                },
+                _ => {
+                    self.finished = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Occurrences<'a> {}
+
+/// RRULEs and RDATEs sharing a DTSTART, expanded together in order without duplicates. This is what VTIMEZONE
+/// transitions use when the crate is built without the `rrule` feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceSet {
+    pub dtstart: NaiveDateTime,
+    pub rules: Vec<IcalRecur>,
+    pub dates: Vec<NaiveDateTime>,
+}
+
+impl RecurrenceSet {
+    /// Each rule is expanded with the default iteration cap, and a rule the expander can't handle contributes nothing.
+    pub fn iter(&self) -> RecurrenceSetIter<'_> {
+        let rules = self.rules.iter()
+            .filter_map(|rule| rule.occurrences_between(self.dtstart, NaiveDateTime::MIN, NaiveDateTime::MAX).ok())
+            .map(Iterator::peekable)
+            .collect();
+        let mut dates = self.dates.clone();
+        dates.sort();
+        dates.dedup();
+
+        RecurrenceSetIter { rules, dates: dates.into() }
+    }
+}
+
+impl<'a> IntoIterator for &'a RecurrenceSet {
+    type Item = NaiveDateTime;
+    type IntoIter = RecurrenceSetIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct RecurrenceSetIter<'a> {
+    rules: Vec<Peekable<Occurrences<'a>>>,
+    dates: VecDeque<NaiveDateTime>,
+}
+
+impl<'a> Iterator for RecurrenceSetIter<'a> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.rules.iter_mut().filter_map(|rule| rule.peek().copied()).chain(self.dates.front().copied()).min()?;
+
+        // Every source is already sorted, so advancing each one past `next` also drops duplicates.
+        for rule in &mut self.rules {
+            rule.next_if_eq(&next);
+        }
+        if self.dates.front() == Some(&next) {
+            self.dates.pop_front();
+        }
+
+        Some(next)
+    }
+}
+
+impl<'a> FusedIterator for RecurrenceSetIter<'a> {}
+
+/// Occurrences converted to UTC. Local times in a DST gap convert with the offset before it, which puts them after
+/// the local times that follow, so instants are held back until no later local time can convert to an earlier one.
+pub struct ZonedOccurrences<'a> {
+    occurrences: Occurrences<'a>,
+    timezone: &'a Timezone,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    until: Option<DateTime<Utc>>,
+    max_offset: Duration,
+    latest_local: Option<NaiveDateTime>,
+    pending: BinaryHeap<Reverse<(DateTime<Utc>, NaiveDateTime)>>,
+    last: Option<DateTime<Utc>>,
+}
+
+impl<'a> ZonedOccurrences<'a> {
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.occurrences = self.occurrences.max_iterations(max_iterations);
+        self
+    }
+
+    pub fn with_options(self, options: &ParseOptions) -> Self {
+        self.max_iterations(options.max_iterations)
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.occurrences.limit_reached()
+    }
+
+    /// Adapts the iterator to also yield each occurrence's local time and abbreviation.
+    pub fn local(self) -> LocalOccurrences<'a> {
+        LocalOccurrences { zoned: self }
+    }
+
+    /// The next instant in strictly increasing UTC order. Two local times that name the same instant yield it once.
+    fn next_pair(&mut self) -> Option<(DateTime<Utc>, NaiveDateTime)> {
+        loop {
+            if let Some(Reverse((utc, _))) = self.pending.peek() {
+                // Local times only increase, so nothing after `latest_local` converts to earlier than this. Once the
+                // local times run out, `latest_local` is cleared and everything pending is settled.
+                let settled = self.latest_local.is_none_or(|latest| latest.checked_sub_signed(self.max_offset).is_none_or(|bound| bound.and_utc() >= *utc));
+                if settled {
+                    let Reverse((utc, local)) = self.pending.pop()?;
+                    if self.last.is_some_and(|last| last >= utc) {
+                        continue;
+                    }
+                    self.last = Some(utc);
+                    return Some((utc, local));
+                }
+            }
+
+            match self.occurrences.next() {
+                Some(local) => {
+                    self.latest_local = Some(local);
+                    // The constructor rejects zones without transitions, so the conversion always has an offset.
+                    let utc = self.timezone.to_utc(local).ok()?;
+                    if utc >= self.start && utc <= self.end && self.until.is_none_or(|until| utc <= until) {
+                        self.pending.push(Reverse((utc, local)));
+                    }
+                },
+                None if self.pending.is_empty() => return None,
+                None => self.latest_local = None,
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for ZonedOccurrences<'a> {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_pair().map(|(utc, _)| utc)
+    }
+}
+
+impl<'a> FusedIterator for ZonedOccurrences<'a> {}
+
+pub struct LocalOccurrences<'a> {
+    zoned: ZonedOccurrences<'a>,
+}
+
+impl<'a> Iterator for LocalOccurrences<'a> {
+    type Item = (DateTime<Utc>, NaiveDateTime, Option<String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (utc, local) = self.zoned.next_pair()?;
+        Some((utc, local, self.zoned.timezone.abbreviation_at(utc).map(str::to_string)))
+    }
+}
+
+impl<'a> FusedIterator for LocalOccurrences<'a> {}
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+fn join_words(words: &[String]) -> String {
+    match words {
+        [] => String::new(),
+        [word] => word.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+impl IcalRecur {
+    /// An English summary such as "Every 2 weeks on Monday and Wednesday, 10 times". Covers the frequency,
+    /// INTERVAL, BYDAY and COUNT or UNTIL; other BY* parts are not described.
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            ICalRecurFrequency::Secondly => "second",
+            ICalRecurFrequency::Minutely => "minute",
+            ICalRecurFrequency::Hourly => "hour",
+            ICalRecurFrequency::Daily => "day",
+            ICalRecurFrequency::Weekly => "week",
+            ICalRecurFrequency::Monthly => "month",
+            ICalRecurFrequency::Yearly => "year",
+        };
+        let mut description = match self.interval.unwrap_or(1) {
+            1 => format!("Every {unit}"),
+            interval => format!("Every {interval} {unit}s"),
+        };
+
+        if let Some(days) = self.by_day.as_ref().filter(|days| !days.is_empty()) {
+            let days = days.iter().map(|day| {
+                let name = weekday_name(day.day);
+                match day.nth_of_month {
+                    None => name.to_string(),
+                    Some(-1) => format!("the last {name}"),
+                    Some(nth) if nth < 0 => format!("the {} to last {name}", ordinal(nth.unsigned_abs().into())),
+                    Some(nth) => format!("the {} {name}", ordinal(nth.unsigned_abs().into())),
+                }
+            }).collect::<Vec<_>>();
+            description.push_str(" on ");
+            description.push_str(&join_words(&days));
+        }
+
+        match &self.limit {
+            Some(IcalRecurLimit::Count(1)) => description.push_str(", once"),
+            Some(IcalRecurLimit::Count(count)) => description.push_str(&format!(", {count} times")),
+            Some(IcalRecurLimit::Until(IcalRecurUntil::Date(date))) => description.push_str(&format!(", until {}", date.format("%b %-d, %Y"))),
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(date_time))) => description.push_str(&format!(", until {}", date_time.format("%b %-d, %Y"))),
+            None => {}
+        }
+
+        description
+    }
+}
+
+fn weekday_name(day: ICalRecurDayOfWeek) -> &'static str {
+    match day {
+        ICalRecurDayOfWeek::Sunday => "Sunday",
+        ICalRecurDayOfWeek::Monday => "Monday",
+        ICalRecurDayOfWeek::Tuesday => "Tuesday",
+        ICalRecurDayOfWeek::Wednesday => "Wednesday",
+        ICalRecurDayOfWeek::Thursday => "Thursday",
+        ICalRecurDayOfWeek::Friday => "Friday",
+        ICalRecurDayOfWeek::Saturday => "Saturday",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ical::property::Property;
+
+    use super::*;
+
+    fn recur(value: &str) -> IcalRecur {
+        IcalRecur::try_from(Property { name: "RRULE".to_string(), params: None, value: Some(value.to_string()) }).unwrap()
+    }
+
+    fn date_time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn estimated_count_bounds_expansion() {
+        let (dtstart, start, end) = (date_time("2024-01-01T09:00:00"), date_time("2024-01-01T00:00:00"), date_time("2024-12-31T23:59:59"));
+        assert_eq!(recur("FREQ=WEEKLY;COUNT=10").estimated_count(dtstart, start, end), 10);
+        assert_eq!(recur("FREQ=WEEKLY").estimated_count(dtstart, start, end), 53);
+        assert_eq!(recur("FREQ=DAILY;UNTIL=20240110").estimated_count(dtstart, start, end), 10);
+        assert_eq!(recur("FREQ=DAILY").estimated_count(dtstart, date_time("2025-01-01T00:00:00"), end), 0);
+
+        let rules = [
+            "FREQ=DAILY", "FREQ=DAILY;INTERVAL=3;BYHOUR=9,17", "FREQ=WEEKLY;BYDAY=MO,WE,FR", "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU",
+            "FREQ=MONTHLY;BYDAY=-1FR", "FREQ=MONTHLY;BYMONTHDAY=1,15,31", "FREQ=MONTHLY;BYDAY=MO,TU;BYSETPOS=1", "FREQ=YEARLY;BYMONTH=3,9;BYDAY=SU",
+            "FREQ=YEARLY;BYWEEKNO=1,20;BYDAY=MO", "FREQ=YEARLY;BYYEARDAY=1,100,-1", "FREQ=HOURLY;INTERVAL=5;BYMINUTE=0,30", "FREQ=MINUTELY;INTERVAL=97",
+        ];
+        for rule in rules {
+            let recur = recur(rule);
+            for start in [start, date_time("2024-03-17T12:34:56")] {
+                let actual = recur.occurrences_between(dtstart, start, end).unwrap().count();
+                let estimate = recur.estimated_count(dtstart, start, end);
+                assert!(estimate >= actual && estimate <= actual * 2 + 2, "{rule} from {start}: estimated {estimate}, expanded {actual}");
+            }
+        }
+    }
+
+    #[test]
+    fn infinite_rule_stops_at_window_end() {
+        let recur = recur("FREQ=DAILY");
+        let occurrences = recur.occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-03-10T00:00:00"), date_time("2024-03-12T23:59:59")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-03-10T09:00:00"), date_time("2024-03-11T09:00:00"), date_time("2024-03-12T09:00:00")]);
+    }
+
+    #[test]
+    fn windows_after_dtstart_match_a_full_expansion() {
+        let dtstart = date_time("2010-01-05T09:30:15");
+        let (start, end) = (date_time("2024-03-10T07:45:00"), date_time("2024-03-24T08:00:00"));
+        let rules = [
+            "FREQ=HOURLY", "FREQ=HOURLY;INTERVAL=7", "FREQ=MINUTELY;INTERVAL=97;BYHOUR=8", "FREQ=DAILY;INTERVAL=3", "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,SU",
+            "FREQ=MONTHLY;BYMONTHDAY=10,24", "FREQ=YEARLY;BYMONTH=3;BYDAY=SU", "FREQ=DAILY;UNTIL=20240315T000000Z",
+        ];
+        for rule in rules {
+            let recur = recur(rule);
+            let full = recur.occurrences_between(dtstart, dtstart, end).unwrap().max_iterations(usize::MAX).filter(|occurrence| *occurrence >= start).collect::<Vec<_>>();
+            let mut windowed = recur.occurrences_between(dtstart, start, end).unwrap();
+            assert_eq!(windowed.by_ref().collect::<Vec<_>>(), full, "{rule}");
+            assert!(!windowed.limit_reached(), "{rule}");
+            assert!(!full.is_empty(), "{rule}");
+        }
+    }
+
+    #[test]
+    fn count_is_measured_from_dtstart() {
+        let recur = recur("FREQ=WEEKLY;COUNT=4;BYDAY=TU,TH");
+        let occurrences = recur.occurrences_between(date_time("1997-09-02T09:00:00"), date_time("1997-09-05T00:00:00"), date_time("1997-12-31T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("1997-09-09T09:00:00"), date_time("1997-09-11T09:00:00")]);
+    }
+
+    #[test]
+    fn iteration_cap_stops_unmatchable_rule() {
+        let recur = recur("FREQ=SECONDLY;BYMONTH=2;BYMONTHDAY=30");
+        let mut occurrences = recur.occurrences_between(date_time("2024-01-01T00:00:00"), date_time("2024-01-01T00:00:00"), date_time("9999-01-01T00:00:00")).unwrap().max_iterations(1_000);
+        assert_eq!(occurrences.next(), Some(date_time("2024-01-01T00:00:00")));
+        assert_eq!(occurrences.next(), None);
+        assert!(occurrences.limit_reached());
+    }
+
+    #[test]
+    fn expands_week_numbers() {
+        let recur_20 = recur("FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO");
+        let occurrences = recur_20.occurrences_between(date_time("1997-05-12T09:00:00"), date_time("1997-01-01T00:00:00"), date_time("1999-12-31T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("1997-05-12T09:00:00"), date_time("1998-05-11T09:00:00"), date_time("1999-05-17T09:00:00")]);
+
+        // Without BYDAY the weekday comes from DTSTART. Only 2020 and 2026 have a week 53 in this span.
+        let recur_53 = recur("FREQ=YEARLY;BYWEEKNO=53");
+        let occurrences = recur_53.occurrences_between(date_time("2020-12-28T09:00:00"), date_time("2020-01-01T00:00:00"), date_time("2027-12-31T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2020-12-28T09:00:00"), date_time("2026-12-28T09:00:00")]);
+
+        // Week 1 of 2025 starts on 2024-12-30, and moving WKST to Sunday shifts the week boundaries.
+        let first = recur("FREQ=YEARLY;BYWEEKNO=1;BYDAY=MO,SU");
+        let occurrences = first.occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-06-01T00:00:00"), date_time("2025-06-01T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-12-30T09:00:00"), date_time("2025-01-05T09:00:00")]);
+        let sunday = recur("FREQ=YEARLY;BYWEEKNO=1;BYDAY=MO,SU;WKST=SU");
+        let occurrences = sunday.occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-06-01T00:00:00"), date_time("2025-06-01T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-12-29T09:00:00"), date_time("2024-12-30T09:00:00")]);
+
+        assert_eq!(recur("FREQ=MONTHLY;BYWEEKNO=1").occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-01-01T00:00:00"), date_time("2025-01-01T00:00:00")).err(), Some(Error::UnsupportedRecurrence));
+    }
+
+    #[test]
+    fn expands_year_days() {
+        let last = recur("FREQ=YEARLY;BYYEARDAY=-1");
+        let occurrences = last.occurrences_between(date_time("2023-12-31T09:00:00"), date_time("2023-01-01T00:00:00"), date_time("2025-12-31T23:59:59")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2023-12-31T09:00:00"), date_time("2024-12-31T09:00:00"), date_time("2025-12-31T09:00:00")]);
+
+        let days = recur("FREQ=YEARLY;BYYEARDAY=1,100,366");
+        let occurrences = days.occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-01-01T00:00:00"), date_time("2025-12-31T23:59:59")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![
+            date_time("2024-01-01T09:00:00"),
+            date_time("2024-04-09T09:00:00"),
+            date_time("2024-12-31T09:00:00"),
+            date_time("2025-01-01T09:00:00"),
+            date_time("2025-04-10T09:00:00"),
+        ]);
+
+        assert_eq!(recur("FREQ=MONTHLY;BYYEARDAY=1").occurrences_between(date_time("2024-01-01T09:00:00"), date_time("2024-01-01T00:00:00"), date_time("2025-01-01T00:00:00")).err(), Some(Error::UnsupportedRecurrence));
+    }
+
+    #[test]
+    fn recurrence_set_merges_rules_and_dates() {
+        let set = RecurrenceSet {
+            dtstart: date_time("2024-01-01T02:00:00"),
+            rules: vec![recur("FREQ=MONTHLY;COUNT=3"), recur("FREQ=MONTHLY;BYMONTHDAY=15;COUNT=2")],
+            dates: vec![date_time("2024-02-01T02:00:00"), date_time("2024-01-20T02:00:00")],
+        };
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![
+            date_time("2024-01-01T02:00:00"),
+            date_time("2024-01-15T02:00:00"),
+            date_time("2024-01-20T02:00:00"),
+            date_time("2024-02-01T02:00:00"),
+            date_time("2024-03-01T02:00:00"),
+        ]);
+    }
+
+    #[test]
+    fn zoned_expansion_keeps_wall_clock_time_across_dst() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTIMEZONE\r\nTZID:America/New_York\r\nBEGIN:STANDARD\r\nDTSTART:20071104T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nEND:STANDARD\r\nBEGIN:DAYLIGHT\r\nDTSTART:20070311T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nEND:DAYLIGHT\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        let calendar = crate::calendar::Calendar::parse(input, &ParseOptions::default()).unwrap();
+        let timezone = &calendar.timezones["America/New_York"];
+
+        let recur = recur("FREQ=DAILY");
+        let start = date_time("2024-03-09T00:00:00").and_utc();
+        let end = date_time("2024-03-11T23:59:59").and_utc();
+        let occurrences = recur.occurrences_between_in(date_time("2024-03-01T09:00:00"), timezone, start, end).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![
+            date_time("2024-03-09T14:00:00").and_utc(),
+            date_time("2024-03-10T13:00:00").and_utc(),
+            date_time("2024-03-11T13:00:00").and_utc(),
+        ]);
+    }
+
+    #[test]
+    fn zoned_expansion_compares_until_in_utc() {
+        let zone = |offset: &str| {
+            let input = format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTIMEZONE\r\nTZID:Fixed\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\nTZOFFSETFROM:{offset}\r\nTZOFFSETTO:{offset}\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n");
+            crate::calendar::Calendar::parse(&input, &ParseOptions::default()).unwrap().timezones.remove("Fixed").unwrap()
+        };
+        let (start, end) = (date_time("2024-01-01T00:00:00").and_utc(), date_time("2024-12-31T00:00:00").and_utc());
+        let dtstart = date_time("2024-01-15T09:00:00");
+
+        // UNTIL is exactly the second instance.
+        let east = zone("+0500");
+        let recur_east = recur("FREQ=DAILY;UNTIL=20240116T040000Z");
+        let occurrences = recur_east.occurrences_between_in(dtstart, &east, start, end).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-01-15T04:00:00").and_utc(), date_time("2024-01-16T04:00:00").and_utc()]);
+
+        // UNTIL falls between the first and second instances.
+        let west = zone("-0500");
+        let recur_west = recur("FREQ=DAILY;UNTIL=20240116T100000Z");
+        let occurrences = recur_west.occurrences_between_in(dtstart, &west, start, end).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-01-15T14:00:00").and_utc()]);
+    }
+
+    #[test]
+    fn describes_rules_in_english() {
+        let describe = |value: &str| recur(value).describe();
+        assert_eq!(describe("FREQ=WEEKLY"), "Every week");
+        assert_eq!(describe("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE"), "Every 2 weeks on Monday and Wednesday");
+        assert_eq!(describe("FREQ=DAILY;COUNT=1"), "Every day, once");
+        assert_eq!(describe("FREQ=MONTHLY;BYDAY=-1FR;COUNT=12"), "Every month on the last Friday, 12 times");
+        assert_eq!(describe("FREQ=MONTHLY;BYDAY=2TU,-2TH"), "Every month on the 2nd Tuesday and the 2nd to last Thursday");
+        assert_eq!(describe("FREQ=YEARLY;UNTIL=20251231"), "Every year, until Dec 31, 2025");
+        assert_eq!(describe("FREQ=WEEKLY;BYDAY=MO,TU,WE;UNTIL=20250105T000000Z"), "Every week on Monday, Tuesday and Wednesday, until Jan 5, 2025");
+    }
+
+    #[test]
+    fn local_occurrences_carry_abbreviations() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTIMEZONE\r\nTZID:America/New_York\r\nBEGIN:STANDARD\r\nDTSTART:20071104T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nTZNAME:EST\r\nEND:STANDARD\r\nBEGIN:DAYLIGHT\r\nDTSTART:20070311T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nTZNAME:EDT\r\nEND:DAYLIGHT\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        let calendar = crate::calendar::Calendar::parse(input, &ParseOptions::default()).unwrap();
+        let timezone = &calendar.timezones["America/New_York"];
+
+        let recur = recur("FREQ=DAILY");
+        let start = date_time("2024-03-09T00:00:00").and_utc();
+        let end = date_time("2024-03-10T23:59:59").and_utc();
+        let occurrences = recur.occurrences_local(date_time("2024-03-01T09:00:00"), timezone, start, end).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![
+            (date_time("2024-03-09T14:00:00").and_utc(), date_time("2024-03-09T09:00:00"), Some("EST".to_string())),
+            (date_time("2024-03-10T13:00:00").and_utc(), date_time("2024-03-10T09:00:00"), Some("EDT".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn zoned_occurrences_increase_across_dst_changes() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTIMEZONE\r\nTZID:America/New_York\r\nBEGIN:STANDARD\r\nDTSTART:20071104T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nEND:STANDARD\r\nBEGIN:DAYLIGHT\r\nDTSTART:20070311T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nEND:DAYLIGHT\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        let calendar = crate::calendar::Calendar::parse(input, &ParseOptions::default()).unwrap();
+        let timezone = &calendar.timezones["America/New_York"];
+
+        let recur = recur("FREQ=MINUTELY;INTERVAL=15");
+        for day in ["2024-03-10", "2024-11-03"] {
+            let dtstart = date_time(&format!("{day}T00:00:00"));
+            let occurrences = recur.occurrences_between_in(dtstart, timezone, dtstart.and_utc(), dtstart.and_utc() + Duration::days(1)).unwrap().collect::<Vec<_>>();
+            assert!(occurrences.windows(2).all(|pair| pair[0] < pair[1]), "{day}: {occurrences:?}");
+            assert!(occurrences.len() > 70);
+        }
+    }
+
+    #[test]
+    fn dtstart_is_anchor_even_when_excluded() {
+        let recur = recur("FREQ=WEEKLY;BYDAY=MO;COUNT=3");
+        let occurrences = recur.occurrences_between(date_time("2024-01-03T10:00:00"), date_time("2024-01-01T00:00:00"), date_time("2024-12-31T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("2024-01-03T10:00:00"), date_time("2024-01-08T10:00:00"), date_time("2024-01-15T10:00:00")]);
+    }
+
+    #[test]
+    fn last_weekday_of_month() {
+        let recur = recur("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1");
+        let occurrences = recur.occurrences_between(date_time("2024-01-31T17:00:00"), date_time("2024-01-01T00:00:00"), date_time("2024-06-30T23:59:59")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![
+            date_time("2024-01-31T17:00:00"), date_time("2024-02-29T17:00:00"), date_time("2024-03-29T17:00:00"),
+            date_time("2024-04-30T17:00:00"), date_time("2024-05-31T17:00:00"), date_time("2024-06-28T17:00:00"),
+        ]);
+    }
+
+    #[test]
+    fn wkst_changes_biweekly_occurrences() {
+        let dtstart = date_time("1997-08-05T09:00:00");
+        let window = (date_time("1997-01-01T00:00:00"), date_time("1997-12-31T00:00:00"));
+
+        let monday = recur("FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO");
+        let occurrences = monday.occurrences_between(dtstart, window.0, window.1).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("1997-08-05T09:00:00"), date_time("1997-08-10T09:00:00"), date_time("1997-08-19T09:00:00"), date_time("1997-08-24T09:00:00")]);
+
+        let sunday = recur("FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU");
+        let occurrences = sunday.occurrences_between(dtstart, window.0, window.1).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![date_time("1997-08-05T09:00:00"), date_time("1997-08-17T09:00:00"), date_time("1997-08-19T09:00:00"), date_time("1997-08-31T09:00:00")]);
+    }
+
+    #[test]
+    fn monthly_nth_weekday() {
+        let recur = recur("FREQ=MONTHLY;COUNT=6;BYDAY=1FR,-1SU");
+        let occurrences = recur.occurrences_between(date_time("1997-09-05T09:00:00"), date_time("1997-01-01T00:00:00"), date_time("1998-12-31T00:00:00")).unwrap().collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![
+            date_time("1997-09-05T09:00:00"), date_time("1997-09-28T09:00:00"),
+            date_time("1997-10-03T09:00:00"), date_time("1997-10-26T09:00:00"),
+            date_time("1997-11-07T09:00:00"), date_time("1997-11-30T09:00:00"),
+        ]);
+    }
+}
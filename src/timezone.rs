@@ -1,43 +1,131 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::{HashMap, VecDeque}, fmt, iter::FusedIterator, ops::{Deref, DerefMut}, sync::{PoisonError, RwLock}};
 
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use ical::parser::ical::component::{IcalTimeZone, IcalTimeZoneTransition};
-use rrule::{RRule, RRuleSet, RRuleSetIter, Tz};
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc};
+use http::Uri;
+use ical::{parser::ical::component::{IcalTimeZone, IcalTimeZoneTransition, IcalTimeZoneTransitionType}, property::Property};
+#[cfg(feature = "rrule")]
+use {std::str::FromStr, chrono::Timelike, rrule::{NWeekday, RRule, RRuleSet, Tz}};
 
-use crate::{types::{self, IcalDateTime}, Error};
+#[cfg(not(feature = "rrule"))]
+use crate::{recur::RecurrenceSet, types::IcalRecur};
+use crate::{recur::DEFAULT_MAX_ITERATIONS, types::{self, IcalDateTime, IcalURI}, DuplicateTimezonePolicy, Error};
 
+/// Whether a transition came from a STANDARD or a DAYLIGHT sub-component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimezoneTransitionKind {
+    #[default]
+    Standard,
+    Daylight,
+}
+
+#[derive(Clone)]
 pub struct TimezoneTransition {
+    pub kind: TimezoneTransitionKind,
     pub local_start_time: NaiveDateTime,
     pub offset: Duration,
-    pub r_rules: Option<RRuleSet>,
+    /// The offset in effect just before each onset, which is what the onset's local time is written in.
+    pub offset_from: Duration,
+    /// The first TZNAME, an abbreviation such as "EDT".
+    pub name: Option<String>,
+    pub r_rules: Option<TransitionRules>,
 }
 
+/// The RRULEs and RDATEs of a transition, expanded by the rrule crate.
+#[cfg(feature = "rrule")]
+pub type TransitionRules = RRuleSet;
+/// The RRULEs and RDATEs of a transition, expanded by the crate's own expander.
+#[cfg(not(feature = "rrule"))]
+pub type TransitionRules = RecurrenceSet;
+
+#[cfg(feature = "rrule")]
 fn make_rrule_datetime(dt: NaiveDateTime) -> DateTime<Tz> {
     DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc).with_timezone(&Tz::UTC)
 }
 
+#[cfg(feature = "rrule")]
+fn transition_rules(dt_start: NaiveDateTime, r_rules: Vec<String>, r_dates: Vec<NaiveDateTime>) -> Result<TransitionRules, Error> {
+    let dt_start = make_rrule_datetime(dt_start);
+    let mut set = RRuleSet::new(dt_start);
+    for rrule in r_rules {
+        let rrule = RRule::from_str(&rrule).or(Err(Error::InvalidTimezone))?;
+        set = set.rrule(rrule.validate(dt_start).map_err(|_| Error::InvalidTimezone)?);
+    }
+
+    for rdate in r_dates {
+        set = set.rdate(make_rrule_datetime(rdate));
+    }
+
+    Ok(set)
+}
+
+#[cfg(not(feature = "rrule"))]
+fn transition_rules(dt_start: NaiveDateTime, r_rules: Vec<String>, r_dates: Vec<NaiveDateTime>) -> Result<TransitionRules, Error> {
+    let rules = r_rules.iter().map(|rrule| rrule.parse::<IcalRecur>()).collect::<Result<Vec<_>, _>>().or(Err(Error::InvalidTimezone))?;
+    for rule in &rules {
+        rule.validate().or(Err(Error::InvalidTimezone))?;
+        rule.occurrences_between(dt_start, dt_start, dt_start).or(Err(Error::InvalidTimezone))?;
+    }
+
+    Ok(RecurrenceSet { dtstart: dt_start, rules, dates: r_dates })
+}
+
+/// RRULE values and RDATEs of a set, for writing it back out.
+#[cfg(feature = "rrule")]
+fn rule_values(r_rules: &TransitionRules, dt_start: NaiveDateTime) -> (Vec<String>, Vec<NaiveDateTime>) {
+    (r_rules.get_rrule().iter().map(|rrule| format_rrule(rrule, dt_start)).collect(), r_rules.get_rdate().iter().map(|rdate| rdate.naive_utc()).collect())
+}
+
+#[cfg(not(feature = "rrule"))]
+fn rule_values(r_rules: &TransitionRules, _dt_start: NaiveDateTime) -> (Vec<String>, Vec<NaiveDateTime>) {
+    (r_rules.rules.iter().map(IcalRecur::to_ical).collect(), r_rules.dates.clone())
+}
+
+#[cfg(feature = "rrule")]
+fn onset(time: DateTime<Tz>) -> NaiveDateTime {
+    time.naive_utc()
+}
+
+#[cfg(not(feature = "rrule"))]
+fn onset(time: NaiveDateTime) -> NaiveDateTime {
+    time
+}
+
 impl TryFrom<IcalTimeZoneTransition> for TimezoneTransition {
     type Error = Error;
 
     fn try_from(value: IcalTimeZoneTransition) -> Result<Self, Self::Error> {
+        let kind = match value.transition {
+            IcalTimeZoneTransitionType::STANDARD => TimezoneTransitionKind::Standard,
+            IcalTimeZoneTransitionType::DAYLIGHT => TimezoneTransitionKind::Daylight,
+        };
         let mut local_start_time = None;
         let mut offset = None;
+        let mut offset_from = None;
+        let mut name = None;
         let mut r_rule_list = Vec::new();
         let mut r_date_list = Vec::new();
 
         for prop in value.properties {
             match prop.name.as_str() {
-                "DTSTART" => local_start_time = prop.value.map(|time| types::date_time_parser::date_time(&time).unwrap()),
-                "TZOFFSETTO" => offset = prop.value.map(|offset| types::timzeone_parser::utc_offset(&offset).unwrap()),
+                "DTSTART" => local_start_time = Some(types::ical_type_parser::date_time(prop.value.as_deref().unwrap_or_default()).map_err(|_| Error::InvalidTimezone)?),
+                "TZOFFSETTO" => offset = Some(types::IcalUTCOffset::try_from(prop).map_err(|_| Error::InvalidTimezone)?.offset),
+                "TZOFFSETFROM" => offset_from = Some(types::IcalUTCOffset::try_from(prop).map_err(|_| Error::InvalidTimezone)?.offset),
+                "TZNAME" => name = name.or(prop.value),
                 "RRULE" => {
-                    let rrule = RRule::from_str(&prop.value.unwrap()).or(Err(Error::InvalidTimezone))?;
-                    r_rule_list.push(rrule);
+                    let r_rule = prop.value.ok_or(Error::InvalidTimezone)?;
+                    // Offsets never change more than daily, and finer rules would flood the onset cache.
+                    let frequency = r_rule.parse::<types::IcalRecur>().map(|recur| recur.frequency);
+                    if matches!(frequency, Ok(types::ICalRecurFrequency::Hourly | types::ICalRecurFrequency::Minutely | types::ICalRecurFrequency::Secondly)) {
+                        return Err(Error::UnsupportedRecurrence);
+                    }
+                    r_rule_list.push(r_rule);
                 },
                 "RDATE" => {
-                    let rdate = types::date_time_parser::date_time(&prop.value.unwrap()).or(Err(Error::InvalidTimezone))?;
-                    match rdate {
-                        IcalDateTime::Floating { date_time } => r_date_list.push(date_time),
-                        _ => return Err(Error::InvalidTimezone)
+                    for rdate in IcalDateTime::list_from(&prop).or(Err(Error::InvalidTimezone))? {
+                        match rdate {
+                            IcalDateTime::Floating { date_time } => r_date_list.push(date_time),
+                            _ => return Err(Error::InvalidTimezone)
+                        }
                     }
                 },
                 _ => {}
@@ -49,80 +137,393 @@ impl TryFrom<IcalTimeZoneTransition> for TimezoneTransition {
             _ => return Err(Error::InvalidTimezone),
         };
         let offset = offset.ok_or(Error::InvalidTimezone)?;
+        let offset_from = offset_from.ok_or(Error::InvalidTimezone)?;
 
         let r_rules = if !r_rule_list.is_empty() || !r_date_list.is_empty() {
-            let dt_start = make_rrule_datetime(local_start_time);
-            let mut r_rules = RRuleSet::new(dt_start);
-            for rrule in r_rule_list {
-                r_rules = r_rules.rrule(rrule.validate(dt_start).map_err(|_| Error::InvalidTimezone)?);
-            }
-
-            for rdate in r_date_list {
-                r_rules = r_rules.rdate(make_rrule_datetime(rdate));
-            }
-
-            Some(r_rules)
+            Some(transition_rules(local_start_time, r_rule_list, r_date_list)?)
         } else {
             None
         };
 
-        Ok(TimezoneTransition { local_start_time, offset, r_rules })
+        Ok(TimezoneTransition { kind, local_start_time, offset, offset_from, name, r_rules })
+    }
+}
+
+fn property(name: &str, value: String) -> Property {
+    Property { name: name.to_string(), params: None, value: Some(value) }
+}
+
+fn format_offset(offset: Duration) -> String {
+    types::IcalUTCOffset { offset }.to_ical()
+}
+
+#[cfg(feature = "rrule")]
+/// Writes an RRULE value. rrule's own `Display` drops the ordinal of `1SU`-style days, which VTIMEZONE rules rely
+/// on, and it lists the BYHOUR/BYMINUTE/BYSECOND that validation copies from DTSTART, which are left out here.
+fn format_rrule(rrule: &RRule, dt_start: NaiveDateTime) -> String {
+    fn list<T: ToString>(name: &str, values: &[T]) -> Option<String> {
+        (!values.is_empty()).then(|| format!("{name}={}", values.iter().map(T::to_string).collect::<Vec<_>>().join(",")))
+    }
+    let day = |weekday: chrono::Weekday| weekday.to_string()[..2].to_uppercase();
+    let by_day = rrule.get_by_weekday().iter().map(|weekday| match *weekday {
+        NWeekday::Every(weekday) => day(weekday),
+        NWeekday::Nth(n, weekday) => format!("{n}{}", day(weekday)),
+    }).collect::<Vec<_>>();
+    let from_start = |values: &[u8], start: u32| values != [start as u8];
+
+    // Same part order as `IcalRecur::to_ical`.
+    let mut parts = vec![format!("FREQ={}", rrule.get_freq().to_string().to_uppercase())];
+    parts.extend(rrule.get_until().map(|until| format!("UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))));
+    parts.extend(rrule.get_count().map(|count| format!("COUNT={count}")));
+    if rrule.get_interval() != 1 {
+        parts.push(format!("INTERVAL={}", rrule.get_interval()));
+    }
+    if from_start(rrule.get_by_second(), dt_start.second()) {
+        parts.extend(list("BYSECOND", rrule.get_by_second()));
+    }
+    if from_start(rrule.get_by_minute(), dt_start.minute()) {
+        parts.extend(list("BYMINUTE", rrule.get_by_minute()));
+    }
+    if from_start(rrule.get_by_hour(), dt_start.hour()) {
+        parts.extend(list("BYHOUR", rrule.get_by_hour()));
+    }
+    parts.extend(list("BYDAY", &by_day));
+    parts.extend(list("BYMONTHDAY", rrule.get_by_month_day()));
+    parts.extend(list("BYYEARDAY", rrule.get_by_year_day()));
+    parts.extend(list("BYWEEKNO", rrule.get_by_week_no()));
+    parts.extend(list("BYMONTH", rrule.get_by_month()));
+    parts.extend(list("BYSETPOS", rrule.get_by_set_pos()));
+    if rrule.get_week_start() != chrono::Weekday::Mon {
+        parts.push(format!("WKST={}", day(rrule.get_week_start())));
+    }
+    parts.join(";")
+}
+
+impl From<TimezoneTransition> for IcalTimeZoneTransition {
+    fn from(value: TimezoneTransition) -> Self {
+        let transition = match value.kind {
+            TimezoneTransitionKind::Standard => IcalTimeZoneTransitionType::STANDARD,
+            TimezoneTransitionKind::Daylight => IcalTimeZoneTransitionType::DAYLIGHT,
+        };
+
+        let mut properties = vec![
+            property("DTSTART", value.local_start_time.format("%Y%m%dT%H%M%S").to_string()),
+            property("TZOFFSETFROM", format_offset(value.offset_from)),
+            property("TZOFFSETTO", format_offset(value.offset)),
+        ];
+        if let Some(name) = value.name {
+            properties.push(property("TZNAME", name));
+        }
+        if let Some(r_rules) = &value.r_rules {
+            let (rrules, rdates) = rule_values(r_rules, value.local_start_time);
+            properties.extend(rrules.into_iter().map(|rrule| property("RRULE", rrule)));
+            properties.extend(rdates.iter().map(|rdate| property("RDATE", rdate.format("%Y%m%dT%H%M%S").to_string())));
+        }
+
+        IcalTimeZoneTransition { transition, properties }
     }
 }
 
+#[cfg(feature = "rrule")]
+fn occurrence_limit(r_rules: &TransitionRules) -> Option<usize> {
+    let counts = r_rules.get_rrule().iter().map(|rrule| rrule.get_count().map(|count| count as usize)).sum::<Option<usize>>()?;
+    Some(counts + r_rules.get_rdate().len())
+}
+
+#[cfg(not(feature = "rrule"))]
+fn occurrence_limit(r_rules: &TransitionRules) -> Option<usize> {
+    let counts = r_rules.rules.iter().map(|rule| match rule.limit {
+        Some(types::IcalRecurLimit::Count(count)) => usize::try_from(count).ok(),
+        _ => None,
+    }).sum::<Option<usize>>()?;
+    Some(counts + r_rules.dates.len())
+}
+
 impl<'a> IntoIterator for &'a TimezoneTransition {
     type Item = (NaiveDateTime, Duration);
     type IntoIter = TimezoneTransitionIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
         let rrule_iter = self.r_rules.as_ref().map(|rrules| rrules.into_iter());
-        TimezoneTransitionIter { offset: self.offset, inital: Some(self.local_start_time), rrule_iter }
+        let rrule_remaining = self.r_rules.as_ref().map_or(Some(0), occurrence_limit);
+        TimezoneTransitionIter { offset: self.offset, inital: Some(self.local_start_time), r_rules: self.r_rules.as_ref(), rrule_iter, rrule_yielded: 0, rrule_remaining }
+    }
+}
+
+impl IntoIterator for TimezoneTransition {
+    type Item = (NaiveDateTime, Duration);
+    type IntoIter = OwnedTimezoneTransitionIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let rrule_remaining = self.r_rules.as_ref().map_or(Some(0), occurrence_limit);
+        OwnedTimezoneTransitionIter { offset: self.offset, inital: Some(self.local_start_time), r_rules: self.r_rules, buffer: VecDeque::new(), rrule_yielded: 0, rrule_remaining }
     }
 }
 
 pub struct TimezoneTransitionIter<'a> {
     offset: Duration,
     inital: Option<NaiveDateTime>,
-    rrule_iter: Option<RRuleSetIter<'a>>,
+    r_rules: Option<&'a TransitionRules>,
+    rrule_iter: Option<<&'a TransitionRules as IntoIterator>::IntoIter>,
+    rrule_yielded: usize,
+    rrule_remaining: Option<usize>,
+}
+
+impl<'a> TimezoneTransitionIter<'a> {
+    /// Detaches the iterator from the transition by cloning its rules, resuming from the same position.
+    pub fn into_owned(self) -> OwnedTimezoneTransitionIter {
+        OwnedTimezoneTransitionIter {
+            offset: self.offset,
+            inital: self.inital,
+            r_rules: self.rrule_iter.and(self.r_rules.cloned()),
+            buffer: VecDeque::new(),
+            rrule_yielded: self.rrule_yielded,
+            rrule_remaining: self.rrule_remaining,
+        }
+    }
 }
 
 impl<'a> Iterator for TimezoneTransitionIter<'a> {
     type Item = (NaiveDateTime, Duration);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match (self.inital.take(), self.rrule_iter.as_mut()) {
-            (Some(time), _) => Some((time, self.offset)),
-            (None, Some(ref mut iter)) => iter.next().map(|time| (time.naive_utc(), self.offset)),
-            (None, None) => None,
+        if let Some(time) = self.inital.take() {
+            return Some((time, self.offset));
+        }
+
+        match self.rrule_iter.as_mut()?.next() {
+            Some(time) => {
+                self.rrule_yielded += 1;
+                self.rrule_remaining = self.rrule_remaining.map(|remaining| remaining.saturating_sub(1));
+                Some((onset(time), self.offset))
+            },
+            None => {
+                self.rrule_iter = None;
+                self.rrule_remaining = Some(0);
+                None
+            }
         }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         let inital = if self.inital.is_some() { 1 } else { 0 };
         let rrule = self.rrule_iter.as_ref().map(|iter| iter.size_hint().0).unwrap_or(0);
-        (inital + rrule, None)
+        (inital + rrule, self.rrule_remaining.map(|remaining| inital + remaining))
     }
 }
 
+impl<'a> FusedIterator for TimezoneTransitionIter<'a> {}
+
+/// Onsets pulled from the rules at a time by `OwnedTimezoneTransitionIter`, which has to restart the rule iterator
+/// for every batch since it can't keep a borrow of its own rules.
+const OWNED_BATCH: usize = 64;
+
+/// Like `TimezoneTransitionIter`, but owns a copy of the transition's rules so it can be stored or returned freely.
+pub struct OwnedTimezoneTransitionIter {
+    offset: Duration,
+    inital: Option<NaiveDateTime>,
+    r_rules: Option<TransitionRules>,
+    buffer: VecDeque<NaiveDateTime>,
+    rrule_yielded: usize,
+    rrule_remaining: Option<usize>,
+}
+
+impl Iterator for OwnedTimezoneTransitionIter {
+    type Item = (NaiveDateTime, Duration);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(time) = self.inital.take() {
+            return Some((time, self.offset));
+        }
+
+        if self.buffer.is_empty() {
+            let r_rules = self.r_rules.as_ref()?;
+            self.buffer.extend(r_rules.into_iter().skip(self.rrule_yielded).take(OWNED_BATCH).map(onset));
+        }
+
+        match self.buffer.pop_front() {
+            Some(time) => {
+                self.rrule_yielded += 1;
+                self.rrule_remaining = self.rrule_remaining.map(|remaining| remaining.saturating_sub(1));
+                Some((time, self.offset))
+            },
+            None => {
+                self.r_rules = None;
+                self.rrule_remaining = Some(0);
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let inital = if self.inital.is_some() { 1 } else { 0 };
+        (inital + self.buffer.len(), self.rrule_remaining.map(|remaining| inital + remaining))
+    }
+}
+
+impl FusedIterator for OwnedTimezoneTransitionIter {}
+
+/// How many days past a lookup the onset cache is filled, so nearby lookups don't rebuild it.
+const ONSET_CACHE_AHEAD_DAYS: i64 = 20 * 366;
+
+/// Every onset of every transition up to `until` (local time), sorted so lookups can binary-search. Entries are
+/// (onset, transition index), and ties keep transition order. At most `DEFAULT_MAX_ITERATIONS` onsets are kept, taken
+/// from the transitions in order.
+#[derive(Clone, Default)]
+struct OnsetCache {
+    until: Option<NaiveDateTime>,
+    local: Vec<(NaiveDateTime, usize)>,
+    utc: Vec<(NaiveDateTime, usize)>,
+}
+
+impl OnsetCache {
+    fn build(transitions: &[TimezoneTransition], until: NaiveDateTime) -> Self {
+        let mut local = Vec::new();
+        let mut utc = Vec::new();
+        let mut budget = DEFAULT_MAX_ITERATIONS;
+        for (index, transition) in transitions.iter().enumerate() {
+            for (onset, _) in transition.into_iter().take_while(|(onset, _)| *onset <= until).take(budget) {
+                budget -= 1;
+                local.push((onset, index));
+                utc.push((onset - transition.offset_from, index));
+            }
+        }
+        local.sort_by_key(|(onset, _)| *onset);
+        utc.sort_by_key(|(onset, _)| *onset);
+
+        OnsetCache { until: Some(until), local, utc }
+    }
+
+    fn latest(onsets: &[(NaiveDateTime, usize)], time: NaiveDateTime) -> Option<(NaiveDateTime, usize)> {
+        let count = onsets.partition_point(|(onset, _)| *onset <= time);
+        count.checked_sub(1).map(|index| onsets[index])
+    }
+}
+
+#[derive(Default)]
+struct Onsets(RwLock<OnsetCache>);
+
+impl Clone for Onsets {
+    fn clone(&self) -> Self {
+        Onsets(RwLock::new(self.0.read().unwrap_or_else(PoisonError::into_inner).clone()))
+    }
+}
+
+/// A parsed VTIMEZONE. Offset lookups expand the transitions' rules once and cache the onsets, growing the cache
+/// as later times are queried; `transitions_mut` drops the cache.
+#[derive(Clone)]
 pub struct Timezone {
     pub tzid: String,
-    pub transitions: Vec<TimezoneTransition>,
+    /// Sorted by `local_start_time` when parsed, with ties in document order.
+    transitions: Vec<TimezoneTransition>,
+    /// Where an updated definition of the zone can be fetched.
+    pub tzurl: Option<Uri>,
+    /// When the definition was last revised. `None` when absent or not a UTC DATE-TIME.
+    pub last_modified: Option<DateTime<Utc>>,
+    onsets: Onsets,
 }
 
 impl Timezone {
-    pub fn offset_time(&self, time: NaiveDateTime) -> Result<NaiveDateTime, Error> {
-        let mut offset = None;
-        let mut last_update = None;
-        for transition in &self.transitions {
-            transition.r_rules.as_ref().unwrap().into_iter();
-            if time >= transition.local_start_time && (last_update.is_none() || transition.local_start_time > last_update.unwrap()) {
-                offset = Some(transition.offset);
-                last_update = Some(transition.local_start_time);
+    /// The UTC offset in effect at a local wall-clock time. Every onset of every transition (its DTSTART plus any
+    /// RRULE/RDATE instances) is compared in local time and the latest one at or before `time` wins. Times skipped
+    /// by the gap a transition opens keep its TZOFFSETFROM, as RFC 5545 specifies, and times before the first onset
+    /// use the earliest transition's offset.
+    pub fn offset_for_local(&self, time: NaiveDateTime) -> Result<Duration, Error> {
+        let latest = self.with_onsets(time, |onsets| OnsetCache::latest(&onsets.local, time)).map(|(onset, index)| {
+            let transition = &self.transitions[index];
+            let in_gap = onset.checked_add_signed(transition.offset - transition.offset_from).is_some_and(|gap_end| time < gap_end);
+            if in_gap { transition.offset_from } else { transition.offset }
+        });
+        let earliest = self.transitions.first().map(|transition| transition.offset);
+
+        latest.or(earliest).ok_or(Error::InvalidTimezone)
+    }
+
+    /// The UTC offset in effect at an instant. Each onset is converted to UTC with its TZOFFSETFROM and the latest one
+    /// at or before `utc` wins. Instants before the first onset use that transition's TZOFFSETFROM.
+    pub fn offset_at(&self, utc: DateTime<Utc>) -> Result<FixedOffset, Error> {
+        let latest = self.transition_at(utc).map(|transition| transition.offset);
+        let earliest = self.transitions.iter().min_by_key(|transition| transition.local_start_time - transition.offset_from).map(|transition| transition.offset_from);
+
+        let offset = latest.or(earliest).ok_or(Error::InvalidTimezone)?;
+        fixed_offset(offset).ok_or(Error::InvalidTimezone)
+    }
+
+    /// The transition whose most recent onset at or before `utc` is the latest, or `None` before the first onset.
+    pub fn transition_at(&self, utc: DateTime<Utc>) -> Option<&TimezoneTransition> {
+        let time = utc.naive_utc();
+        // A UTC onset's local time is less than a day later, so local onsets up to a day past `time` cover it.
+        let horizon = time.checked_add_signed(Duration::days(1)).unwrap_or(NaiveDateTime::MAX);
+        self.with_onsets(horizon, |onsets| OnsetCache::latest(&onsets.utc, time)).map(|(_, index)| &self.transitions[index])
+    }
+
+    /// Every onset of every transition within `[start, end]`, in order, with the offset and kind it switches to.
+    pub fn transition_points_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(DateTime<Utc>, FixedOffset, TimezoneTransitionKind)> {
+        let (start, end) = (start.naive_utc(), end.naive_utc());
+        let horizon = end.checked_add_signed(Duration::days(1)).unwrap_or(NaiveDateTime::MAX);
+        self.with_onsets(horizon, |onsets| {
+            let first = onsets.utc.partition_point(|(onset, _)| *onset < start);
+            onsets.utc[first..].iter()
+                .take_while(|(onset, _)| *onset <= end)
+                .filter_map(|(onset, index)| {
+                    let transition = &self.transitions[*index];
+                    Some((onset.and_utc(), fixed_offset(transition.offset)?, transition.kind))
+                })
+                .collect()
+        })
+    }
+
+    /// Sorted by `local_start_time` when parsed, with ties in document order.
+    pub fn transitions(&self) -> &[TimezoneTransition] {
+        &self.transitions
+    }
+
+    /// The transitions for editing. The onset cache is dropped and rebuilt by the next lookup.
+    pub fn transitions_mut(&mut self) -> &mut Vec<TimezoneTransition> {
+        self.onsets = Onsets::default();
+        &mut self.transitions
+    }
+
+    /// Runs `lookup` against a cache holding every onset up to local time `time`, extending the cache first if needed.
+    fn with_onsets<T>(&self, time: NaiveDateTime, lookup: impl FnOnce(&OnsetCache) -> T) -> T {
+        let covered = |cache: &OnsetCache| cache.until.is_some_and(|until| until >= time);
+        {
+            let cache = self.onsets.0.read().unwrap_or_else(PoisonError::into_inner);
+            if covered(&cache) {
+                return lookup(&cache);
             }
         }
-        
-        let offset = offset.ok_or(Error::InvalidTimezone)?;
-        Ok(time + offset)
+
+        let mut cache = self.onsets.0.write().unwrap_or_else(PoisonError::into_inner);
+        if !covered(&cache) {
+            let until = time.checked_add_signed(Duration::days(ONSET_CACHE_AHEAD_DAYS)).unwrap_or(NaiveDateTime::MAX);
+            *cache = OnsetCache::build(&self.transitions, until);
+        }
+        lookup(&cache)
+    }
+
+    /// Whether the TZID is a zone name known to the IANA database bundled with chrono-tz. Backward-compatible
+    /// links such as "US/Eastern" count as known.
+    #[cfg(feature = "chrono-tz")]
+    pub fn validate_iana(&self) -> bool {
+        self.tzid.parse::<chrono_tz::Tz>().is_ok()
+    }
+
+    /// The TZNAME abbreviation in effect at an instant, if the zone gives one.
+    pub fn abbreviation_at(&self, utc: DateTime<Utc>) -> Option<&str> {
+        self.transition_at(utc)?.name.as_deref()
+    }
+
+    /// Wraps the timezone for use with chrono's generic APIs, such as `DateTime::with_timezone`.
+    pub fn as_chrono(&self) -> Result<ChronoTimezone<'_>, Error> {
+        if self.transitions.is_empty() {
+            return Err(Error::InvalidTimezone);
+        }
+        Ok(ChronoTimezone { timezone: self })
+    }
+
+    pub fn offset_time(&self, time: NaiveDateTime) -> Result<NaiveDateTime, Error> {
+        time.checked_sub_signed(self.offset_for_local(time)?).ok_or(Error::InvalidDateTime)
     }
 
     pub fn to_utc(&self, time: NaiveDateTime) -> Result<DateTime<Utc>, Error> {
@@ -130,25 +531,471 @@ impl Timezone {
     }
 }
 
+fn fixed_offset(offset: Duration) -> Option<FixedOffset> {
+    i32::try_from(offset.num_seconds()).ok().and_then(FixedOffset::east_opt)
+}
+
+/// A `chrono::TimeZone` backed by a parsed VTIMEZONE. Built with `Timezone::as_chrono`, which guarantees at least
+/// one transition so every UTC instant has an offset.
+#[derive(Clone, Copy)]
+pub struct ChronoTimezone<'a> {
+    timezone: &'a Timezone,
+}
+
+#[derive(Clone, Copy)]
+pub struct ChronoTimezoneOffset<'a> {
+    timezone: &'a Timezone,
+    offset: FixedOffset,
+}
+
+impl<'a> ChronoTimezone<'a> {
+    fn offset(&self, offset: FixedOffset) -> ChronoTimezoneOffset<'a> {
+        ChronoTimezoneOffset { timezone: self.timezone, offset }
+    }
+}
+
+impl<'a> Offset for ChronoTimezoneOffset<'a> {
+    fn fix(&self) -> FixedOffset {
+        self.offset
+    }
+}
+
+impl<'a> fmt::Debug for ChronoTimezoneOffset<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({})", self.offset, self.timezone.tzid)
+    }
+}
+
+impl<'a> fmt::Display for ChronoTimezoneOffset<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.offset, f)
+    }
+}
+
+impl<'a> TimeZone for ChronoTimezone<'a> {
+    type Offset = ChronoTimezoneOffset<'a>;
+
+    fn from_offset(offset: &Self::Offset) -> Self {
+        ChronoTimezone { timezone: offset.timezone }
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<Self::Offset> {
+        self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+    }
+
+    /// A local time is valid under an offset when that offset is the one in effect at the resulting instant. Times
+    /// skipped by a forward transition have no valid offset and times repeated by a backward one have two.
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<Self::Offset> {
+        let mut candidates = self.timezone.transitions.iter()
+            .flat_map(|transition| [transition.offset, transition.offset_from])
+            .filter_map(fixed_offset)
+            .filter(|offset| self.timezone.offset_at((*local - Duration::seconds(offset.local_minus_utc().into())).and_utc()) == Ok(*offset))
+            .collect::<Vec<_>>();
+        // The larger offset reaches UTC first, so it is the earlier of two readings.
+        candidates.sort_by_key(|offset| -offset.local_minus_utc());
+        candidates.dedup();
+
+        match candidates[..] {
+            [] => LocalResult::None,
+            [offset] => LocalResult::Single(self.offset(offset)),
+            [earliest, .., latest] => LocalResult::Ambiguous(self.offset(earliest), self.offset(latest)),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> Self::Offset {
+        self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> Self::Offset {
+        // as_chrono rejects zones without transitions, so offset_at only fails on offsets chrono can't represent.
+        self.offset(self.timezone.offset_at(utc.and_utc()).unwrap_or(Utc.fix()))
+    }
+}
+
 impl TryFrom<IcalTimeZone> for Timezone {
     type Error = Error;
 
     fn try_from(value: IcalTimeZone) -> Result<Self, Self::Error> {
         let mut tzid = None;
+        let mut tzurl = None;
+        let mut last_modified = None;
         for prop in value.properties {
-            if prop.name == "TZID" {
-                tzid = prop.value;
+            match prop.name.as_str() {
+                "TZID" => tzid = prop.value,
+                "TZURL" => tzurl = Some(IcalURI::try_from(prop)?.value),
+                // Only bookkeeping, so a malformed or non-UTC value is dropped rather than losing the zone.
+                "LAST-MODIFIED" => if let Ok(IcalDateTime::Utc { date_time }) = IcalDateTime::try_from(prop) {
+                    last_modified = Some(date_time);
+                },
+                _ => {}
             }
         }
 
         let tzid = tzid.ok_or(Error::InvalidTimezone)?;
 
 
-        let transitions = value.transitions.into_iter().map(|transition| transition.try_into()).collect::<Result<Vec<_>, _>>()?;
+        let mut transitions = value.transitions.into_iter().map(|transition| transition.try_into()).collect::<Result<Vec<TimezoneTransition>, _>>()?;
+        transitions.sort_by_key(|transition| transition.local_start_time);
+
+        Ok(Timezone { tzid, transitions, tzurl, last_modified, onsets: Onsets::default() })
+
+    }
+}
+
+impl From<Timezone> for IcalTimeZone {
+    fn from(value: Timezone) -> Self {
+        let mut properties = vec![property("TZID", value.tzid)];
+        if let Some(tzurl) = value.tzurl {
+            properties.push(property("TZURL", tzurl.to_string()));
+        }
+        if let Some(last_modified) = value.last_modified {
+            properties.push(property("LAST-MODIFIED", last_modified.format("%Y%m%dT%H%M%SZ").to_string()));
+        }
+
+        let transitions = value.transitions.into_iter().map(IcalTimeZoneTransition::from).collect();
+        IcalTimeZone { properties, transitions }
+    }
+}
+
+/// Timezones keyed by TZID.
+#[derive(Clone, Default)]
+pub struct TimezoneMap(HashMap<String, Timezone>);
+
+impl TimezoneMap {
+    pub fn new() -> Self {
+        TimezoneMap(HashMap::new())
+    }
+
+    /// Builds the map from VTIMEZONE components. An identical repeat of a VTIMEZONE is ignored; a differing one
+    /// either replaces the earlier definition or, with `DuplicateTimezonePolicy::Reject`, fails with
+    /// `Error::DuplicateTimezone`.
+    pub fn from_components(components: Vec<IcalTimeZone>, policy: DuplicateTimezonePolicy) -> Result<Self, Error> {
+        let mut timezones = TimezoneMap::new();
+        let mut seen: HashMap<String, IcalTimeZone> = HashMap::new();
+        for component in components {
+            let timezone = Timezone::try_from(component.clone())?;
+            if let Some(previous) = seen.get(&timezone.tzid) {
+                if same_timezone_component(previous, &component) {
+                    continue;
+                }
+                if policy == DuplicateTimezonePolicy::Reject {
+                    return Err(Error::DuplicateTimezone(timezone.tzid));
+                }
+            }
+            seen.insert(timezone.tzid.clone(), component);
+            timezones.insert(timezone.tzid.clone(), timezone);
+        }
+
+        Ok(timezones)
+    }
+}
 
-        Ok(Timezone { tzid, transitions })
+fn same_properties(a: &[Property], b: &[Property]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.name == b.name && a.params == b.params && a.value == b.value)
+}
+
+fn same_timezone_component(a: &IcalTimeZone, b: &IcalTimeZone) -> bool {
+    same_properties(&a.properties, &b.properties)
+        && a.transitions.len() == b.transitions.len()
+        && a.transitions.iter().zip(&b.transitions).all(|(a, b)| {
+            matches!((&a.transition, &b.transition), (IcalTimeZoneTransitionType::STANDARD, IcalTimeZoneTransitionType::STANDARD) | (IcalTimeZoneTransitionType::DAYLIGHT, IcalTimeZoneTransitionType::DAYLIGHT))
+                && same_properties(&a.properties, &b.properties)
+        })
+}
 
+impl Deref for TimezoneMap {
+    type Target = HashMap<String, Timezone>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-pub type TimezoneMap = HashMap<String, Timezone>;
\ No newline at end of file
+impl DerefMut for TimezoneMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<HashMap<String, Timezone>> for TimezoneMap {
+    fn from(value: HashMap<String, Timezone>) -> Self {
+        TimezoneMap(value)
+    }
+}
+
+impl IntoIterator for TimezoneMap {
+    type Item = (String, Timezone);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Timezone>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Converts a wall-clock time in an IANA zone to UTC. Ambiguous times take the earlier instant and times skipped by
+/// a gap use the offset from before the gap, as RFC 5545 specifies.
+#[cfg(feature = "chrono-tz")]
+fn iana_to_utc(zone: chrono_tz::Tz, date_time: NaiveDateTime) -> Result<DateTime<Utc>, Error> {
+    if let Some(local) = zone.from_local_datetime(&date_time).earliest() {
+        return Ok(local.with_timezone(&Utc));
+    }
+    // Step back to the last valid time before the gap for its offset. Gaps are at most a day long.
+    let before = (1..=2 * 24 * 4)
+        .find_map(|quarters| zone.from_local_datetime(&(date_time - Duration::minutes(15 * quarters))).latest())
+        .ok_or(Error::InvalidDateTime)?;
+    date_time.checked_sub_signed(Duration::seconds(before.offset().fix().local_minus_utc().into()))
+        .map(|date_time| date_time.and_utc())
+        .ok_or(Error::InvalidDateTime)
+}
+
+/// Converts a zoned wall-clock time to UTC with its VTIMEZONE. With the `chrono-tz` feature, a TZID without one
+/// falls back to the IANA zone of that name, as exports that omit VTIMEZONE components expect.
+pub(crate) fn zoned_to_utc(date_time: NaiveDateTime, tzid: &str, timezone_map: &TimezoneMap) -> Result<DateTime<Utc>, Error> {
+    if let Some(timezone) = timezone_map.get(tzid) {
+        return timezone.to_utc(date_time);
+    }
+    #[cfg(feature = "chrono-tz")]
+    if let Ok(zone) = tzid.parse::<chrono_tz::Tz>() {
+        return iana_to_utc(zone, date_time);
+    }
+    Err(Error::InvalidTimezone)
+}
+
+/// The wall-clock time of a UTC instant in a TZID, resolved like `zoned_to_utc`. `None` if the zone is unknown.
+pub(crate) fn utc_to_zoned(date_time: DateTime<Utc>, tzid: &str, timezone_map: &TimezoneMap) -> Option<NaiveDateTime> {
+    if let Some(timezone) = timezone_map.get(tzid) {
+        return timezone.offset_at(date_time).ok().map(|offset| date_time.with_timezone(&offset).naive_local());
+    }
+    #[cfg(feature = "chrono-tz")]
+    if let Ok(zone) = tzid.parse::<chrono_tz::Tz>() {
+        return Some(date_time.with_timezone(&zone).naive_local());
+    }
+    None
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ical::IcalParser;
+
+    use super::*;
+
+    /// A zone named `Test` fixed at +01:00, shared by tests across the crate.
+    pub(crate) const TEST_VTIMEZONE: &str = "BEGIN:VTIMEZONE\r\nTZID:Test\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0100\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n";
+
+    /// A map holding only `TEST_VTIMEZONE`.
+    pub(crate) fn test_timezones() -> TimezoneMap {
+        TimezoneMap::from_components(components(&format!("BEGIN:VCALENDAR\r\n{TEST_VTIMEZONE}END:VCALENDAR\r\n")), DuplicateTimezonePolicy::KeepLast).unwrap()
+    }
+
+    fn components(input: &str) -> Vec<IcalTimeZone> {
+        IcalParser::new(input.as_bytes()).next().unwrap().unwrap().timezones
+    }
+
+    pub(crate) fn new_york() -> Timezone {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VTIMEZONE\r\nTZID:America/New_York\r\nBEGIN:STANDARD\r\nDTSTART:20071104T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nTZNAME:EST\r\nEND:STANDARD\r\nBEGIN:DAYLIGHT\r\nDTSTART:20070311T020000\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nTZNAME:EDT\r\nEND:DAYLIGHT\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        Timezone::try_from(components(input).remove(0)).unwrap()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        value.parse::<NaiveDateTime>().unwrap().and_utc()
+    }
+
+    #[test]
+    fn transition_rules_are_bounded() {
+        let zone = |frequency: &str| format!("BEGIN:VCALENDAR\r\nBEGIN:VTIMEZONE\r\nTZID:Busy\r\n\
+            BEGIN:STANDARD\r\nDTSTART:19700101T000000\r\nRRULE:FREQ={frequency}\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0000\r\nEND:STANDARD\r\n\
+            BEGIN:DAYLIGHT\r\nDTSTART:19700101T120000\r\nRRULE:FREQ={frequency}\r\nTZOFFSETFROM:+0000\r\nTZOFFSETTO:+0100\r\nEND:DAYLIGHT\r\n\
+            END:VTIMEZONE\r\nEND:VCALENDAR\r\n");
+        for frequency in ["HOURLY", "MINUTELY", "SECONDLY"] {
+            assert_eq!(Timezone::try_from(components(&zone(frequency)).remove(0)).err(), Some(Error::UnsupportedRecurrence));
+        }
+
+        // Two daily rules reach past the cap well before 2200.
+        let timezone = Timezone::try_from(components(&zone("DAILY")).remove(0)).unwrap();
+        assert!(timezone.offset_for_local(utc("2200-01-01T06:00:00").naive_utc()).is_ok());
+        assert_eq!(timezone.onsets.0.read().unwrap().local.len(), DEFAULT_MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn gap_times_keep_the_offset_from_before_the_gap() {
+        let timezone = new_york();
+        let to_utc = |local: &str| timezone.to_utc(local.parse().unwrap());
+        // Clocks jump from 02:00 EST to 03:00 EDT on 2024-03-10.
+        assert_eq!(to_utc("2024-03-10T01:59:00"), Ok(utc("2024-03-10T06:59:00")));
+        assert_eq!(to_utc("2024-03-10T02:30:00"), Ok(utc("2024-03-10T07:30:00")));
+        assert_eq!(to_utc("2024-03-10T03:00:00"), Ok(utc("2024-03-10T07:00:00")));
+        // The repeated hour in November takes the earlier, EDT reading.
+        assert_eq!(to_utc("2024-11-03T01:30:00"), Ok(utc("2024-11-03T05:30:00")));
+    }
+
+    #[test]
+    fn chrono_timezone_converts_and_detects_gaps() {
+        let timezone = new_york();
+        let zone = timezone.as_chrono().unwrap();
+
+        let local = utc("2024-07-15T12:00:00").with_timezone(&zone);
+        assert_eq!(local.naive_local(), "2024-07-15T08:00:00".parse().unwrap());
+        assert_eq!(local.offset().fix(), FixedOffset::west_opt(4 * 3600).unwrap());
+
+        let local = |value: &str| zone.from_local_datetime(&value.parse().unwrap()).map(|date_time| date_time.with_timezone(&Utc));
+        assert_eq!(local("2024-01-15T09:00:00"), LocalResult::Single(utc("2024-01-15T14:00:00")));
+        assert_eq!(local("2024-03-10T02:30:00"), LocalResult::None);
+        assert_eq!(local("2024-11-03T01:30:00"), LocalResult::Ambiguous(utc("2024-11-03T05:30:00"), utc("2024-11-03T06:30:00")));
+    }
+
+    #[test]
+    fn lists_transition_points_in_order() {
+        let timezone = new_york();
+        let points = timezone.transition_points_between(utc("2023-06-01T00:00:00"), utc("2024-11-03T06:00:00"));
+        let (edt, est) = (FixedOffset::west_opt(4 * 3600).unwrap(), FixedOffset::west_opt(5 * 3600).unwrap());
+        assert_eq!(points, vec![
+            (utc("2023-11-05T06:00:00"), est, TimezoneTransitionKind::Standard),
+            (utc("2024-03-10T07:00:00"), edt, TimezoneTransitionKind::Daylight),
+            (utc("2024-11-03T06:00:00"), est, TimezoneTransitionKind::Standard),
+        ]);
+        assert!(timezone.transition_points_between(utc("2024-04-01T00:00:00"), utc("2024-10-01T00:00:00")).is_empty());
+    }
+
+    #[test]
+    fn transitions_are_sorted_by_start() {
+        // The fixture lists STANDARD (November 2007) before DAYLIGHT (March 2007).
+        let starts = new_york().transitions.iter().map(|transition| transition.local_start_time).collect::<Vec<_>>();
+        assert_eq!(starts, vec!["2007-03-11T02:00:00".parse().unwrap(), "2007-11-04T02:00:00".parse().unwrap()]);
+    }
+
+    #[test]
+    fn owned_iterator_matches_borrowed() {
+        let timezone = new_york();
+        let transition = timezone.transitions[0].clone();
+        let borrowed = (&transition).into_iter().take(100).collect::<Vec<_>>();
+
+        let mut partial = (&transition).into_iter();
+        let head = partial.by_ref().take(3).collect::<Vec<_>>();
+        let tail = partial.into_owned().take(97).collect::<Vec<_>>();
+        assert_eq!([head, tail].concat(), borrowed);
+
+        let owned = transition.into_iter().take(100).collect::<Vec<_>>();
+        assert_eq!(owned, borrowed);
+    }
+
+    #[test]
+    fn rdate_lists_add_every_onset() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VTIMEZONE\r\nTZID:Test\r\nBEGIN:STANDARD\r\nDTSTART:20000101T000000\r\nRDATE:20010101T000000,20020101T000000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0000\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        let timezone = Timezone::try_from(components(input).remove(0)).unwrap();
+        let onsets = (&timezone.transitions[0]).into_iter().map(|(onset, _)| onset).collect::<Vec<_>>();
+        assert!(onsets.contains(&"2001-01-01T00:00:00".parse().unwrap()));
+        assert!(onsets.contains(&"2002-01-01T00:00:00".parse().unwrap()));
+    }
+
+    #[test]
+    fn abbreviation_follows_dst() {
+        let timezone = new_york();
+        assert_eq!(timezone.abbreviation_at(utc("2024-01-15T12:00:00")), Some("EST"));
+        assert_eq!(timezone.abbreviation_at(utc("2024-07-15T12:00:00")), Some("EDT"));
+        assert_eq!(timezone.abbreviation_at(utc("2000-01-01T00:00:00")), None);
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn validates_iana_names() {
+        assert!(new_york().validate_iana());
+
+        let mut timezone = new_york();
+        timezone.tzid = "America/Gotham".to_string();
+        assert!(!timezone.validate_iana());
+    }
+
+    #[test]
+    fn parses_tzurl_and_last_modified() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VTIMEZONE\r\nTZID:Europe/Paris\r\nTZURL:http://tzurl.org/zoneinfo/Europe/Paris\r\nLAST-MODIFIED:20230101T120000Z\r\nBEGIN:STANDARD\r\nDTSTART:19961027T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n";
+        let timezone = Timezone::try_from(components(input).remove(0)).unwrap();
+        assert_eq!(timezone.tzurl, Some(Uri::from_static("http://tzurl.org/zoneinfo/Europe/Paris")));
+        assert_eq!(timezone.last_modified, Some(utc("2023-01-01T12:00:00")));
+        assert_eq!(new_york().tzurl, None);
+
+        for value in ["20230101T120000", "2023-01-01"] {
+            let input = input.replace("20230101T120000Z", value);
+            let timezone = Timezone::try_from(components(&input).remove(0)).unwrap();
+            assert_eq!(timezone.last_modified, None);
+            assert_eq!(timezone.transitions.len(), 1);
+        }
+    }
+
+    #[test]
+    fn malformed_transition_values_are_errors() {
+        for (dtstart, offset) in [("20071304T020000", "-0500"), ("20071104T020000", "-05"), ("20071104", "-0500")] {
+            let input = format!("BEGIN:VCALENDAR\r\nBEGIN:VTIMEZONE\r\nTZID:Test\r\nBEGIN:STANDARD\r\nDTSTART:{dtstart}\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:{offset}\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\nEND:VCALENDAR\r\n");
+            assert!(matches!(Timezone::try_from(components(&input).remove(0)), Err(Error::InvalidTimezone)), "{dtstart} {offset}");
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ical_component() {
+        let original = new_york();
+        let component = IcalTimeZone::from(original.clone());
+        let standard = &component.transitions[1];
+        assert!(matches!(standard.transition, IcalTimeZoneTransitionType::STANDARD));
+        let value = |name: &str| standard.properties.iter().find(|prop| prop.name == name).and_then(|prop| prop.value.clone());
+        assert_eq!(value("DTSTART").as_deref(), Some("20071104T020000"));
+        assert_eq!(value("TZOFFSETFROM").as_deref(), Some("-0400"));
+        assert_eq!(value("TZOFFSETTO").as_deref(), Some("-0500"));
+        assert_eq!(value("TZNAME").as_deref(), Some("EST"));
+        assert_eq!(value("RRULE").as_deref(), Some("FREQ=YEARLY;BYDAY=1SU;BYMONTH=11"));
+
+        let parsed = Timezone::try_from(component).unwrap();
+        assert_eq!(parsed.tzid, original.tzid);
+        let kinds = parsed.transitions.iter().map(|transition| transition.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![TimezoneTransitionKind::Daylight, TimezoneTransitionKind::Standard]);
+        for (before, after) in original.transitions.iter().zip(&parsed.transitions) {
+            assert_eq!(before.into_iter().take(40).collect::<Vec<_>>(), after.into_iter().take(40).collect::<Vec<_>>());
+        }
+        for instant in ["2024-01-15T12:00:00", "2024-03-10T06:59:59", "2024-03-10T07:00:00", "2024-11-03T06:00:00"] {
+            assert_eq!(parsed.offset_at(utc(instant)), original.offset_at(utc(instant)));
+        }
+    }
+
+    #[test]
+    fn cached_lookups_match_across_horizons() {
+        let timezone = new_york();
+        let instants = ["2090-07-01T12:00:00", "2024-01-15T12:00:00", "2024-03-10T07:00:00", "2250-01-01T00:00:00", "2007-11-04T05:59:59"];
+        for instant in instants {
+            let fresh = new_york();
+            assert_eq!(timezone.offset_at(utc(instant)), fresh.offset_at(utc(instant)));
+            assert_eq!(timezone.offset_for_local(utc(instant).naive_utc()), fresh.offset_for_local(utc(instant).naive_utc()));
+        }
+        assert_eq!(timezone.offset_at(utc("2250-07-01T12:00:00")).unwrap().local_minus_utc(), -4 * 3600);
+
+        let mut edited = timezone.clone();
+        edited.transitions_mut().retain(|transition| transition.kind == TimezoneTransitionKind::Standard);
+        assert_eq!(edited.offset_at(utc("2024-07-15T12:00:00")).unwrap().local_minus_utc(), -5 * 3600);
+    }
+
+    #[test]
+    fn offset_at_follows_dst() {
+        let timezone = new_york();
+        assert_eq!(timezone.offset_at(utc("2024-01-15T12:00:00")), Ok(FixedOffset::west_opt(5 * 3600).unwrap()));
+        assert_eq!(timezone.offset_at(utc("2024-07-15T12:00:00")), Ok(FixedOffset::west_opt(4 * 3600).unwrap()));
+        // 2024-03-10 02:00 EST is 07:00 UTC.
+        assert_eq!(timezone.offset_at(utc("2024-03-10T06:59:59")), Ok(FixedOffset::west_opt(5 * 3600).unwrap()));
+        assert_eq!(timezone.offset_at(utc("2024-03-10T07:00:00")), Ok(FixedOffset::west_opt(4 * 3600).unwrap()));
+        // Before the first onset the first transition's TZOFFSETFROM applies.
+        assert_eq!(timezone.offset_at(utc("2000-01-01T00:00:00")), Ok(FixedOffset::west_opt(5 * 3600).unwrap()));
+    }
+
+    #[test]
+    fn duplicate_tzids_follow_the_policy() {
+        let block = |offset| format!("BEGIN:VTIMEZONE\r\nTZID:Test\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\nTZOFFSETFROM:{offset}\r\nTZOFFSETTO:{offset}\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n");
+        let input = format!("BEGIN:VCALENDAR\r\n{}{}END:VCALENDAR\r\n", block("+0100"), block("+0200"));
+
+        let timezones = TimezoneMap::from_components(components(&input), DuplicateTimezonePolicy::KeepLast).unwrap();
+        assert_eq!(timezones.len(), 1);
+        assert_eq!(timezones["Test"].transitions[0].offset, Duration::hours(2));
+
+        let error = TimezoneMap::from_components(components(&input), DuplicateTimezonePolicy::Reject).err();
+        assert_eq!(error, Some(Error::DuplicateTimezone("Test".to_string())));
+
+        let repeated = format!("BEGIN:VCALENDAR\r\n{}{}END:VCALENDAR\r\n", block("+0100"), block("+0100"));
+        let timezones = TimezoneMap::from_components(components(&repeated), DuplicateTimezonePolicy::Reject).unwrap();
+        assert_eq!(timezones["Test"].transitions[0].offset, Duration::hours(1));
+    }
+}
\ No newline at end of file
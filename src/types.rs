@@ -1,26 +1,47 @@
-use std::str::FromStr;
+use std::{borrow::Cow, collections::HashMap, fmt, ops::Deref, str::FromStr};
 
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc, Duration};
 use base64::{engine::general_purpose::STANDARD, Engine as _};
-use ical::property::Property;
+use ical::{parser::ical::component::{IcalAlarm, IcalCalendar, IcalEvent, IcalFreeBusy, IcalJournal, IcalTimeZone, IcalTimeZoneTransition, IcalTodo}, property::Property};
 use http::Uri;
 
-use crate::{Error, ICalTypes};
+use crate::{timezone::{zoned_to_utc, TimezoneMap}, Error, ICalTypes};
+
+/// Largest COUNT accepted in an RRULE. Expansion is lazy and separately capped by `ParseOptions::max_iterations`,
+/// so this only rejects values that can't be meant literally.
+pub const MAX_RECUR_COUNT: u64 = 1_000_000;
+
+/// Parses a run of digits matched by the grammar, as a grammar error rather than a panic if it doesn't fit `T`.
+fn number<T: FromStr>(digits: &str) -> Result<T, &'static str> {
+    digits.parse().map_err(|_| "number out of range")
+}
+
+fn duration_component(digits: &str, unit: fn(i64) -> Option<Duration>, rest: Option<Duration>) -> Result<Duration, &'static str> {
+    digits.parse().ok()
+        .and_then(unit)
+        .and_then(|duration| duration.checked_add(&rest.unwrap_or(Duration::zero())))
+        .ok_or("duration out of range")
+}
 
 peg::parser! {
     pub grammar ical_type_parser() for str {
+        rule i(literal: &'static str)
+            = input:$([_]*<{literal.len()}>) {?
+                if input.eq_ignore_ascii_case(literal) { Ok(()) } else { Err(literal) }
+            }
+
         pub rule boolean() -> bool
-            = "TRUE" { true }
-            / "FALSE" { false }
+            = i("TRUE") { true }
+            / i("FALSE") { false }
 
         pub rule date() -> NaiveDate
-            = year:$(['0'..='9']{4}) month:$(['0'..='9']{2}) day:$(['0'..='9']{2}) {
-                ?NaiveDate::from_ymd_opt(year.parse().unwrap(), month.parse().unwrap(), day.parse().unwrap()).ok_or("Invalid date")
+            = year:$(['0'..='9']*<4>) month:$(['0'..='9']*<2>) day:$(['0'..='9']*<2>) {?
+                NaiveDate::from_ymd_opt(number(year)?, number(month)?, number(day)?).ok_or("Invalid date")
             }
 
         rule raw_time() -> NaiveTime
-            = hour:$(['0'..='9']{2}) minute:$(['0'..='9']{2}) second:$(['0'..='9']{2}) {
-               ?NaiveTime::from_hms_opt(hour.parse().unwrap(), minute.parse().unwrap(), second.parse().unwrap()).ok_or("Invalid time")
+            = hour:$(['0'..='9']*<2>) minute:$(['0'..='9']*<2>) second:$(['0'..='9']*<2>) {?
+               NaiveTime::from_hms_opt(number(hour)?, number(minute)?, number(second)?).ok_or("Invalid time")
             }
 
         pub rule time() -> ICalTime
@@ -49,24 +70,43 @@ peg::parser! {
                 }
             }
 
+            pub rule date_time_list() -> Vec<IcalDateTime>
+                = date_times:(date_time() ++ ",") {
+                    date_times
+                }
+
+            rule numeric_offset() -> Duration
+                = negative:pm_negative() hours:$(['0'..='9']*<2>) ":"? minutes:$(['0'..='9']*<2>) {?
+                    let offset = duration_component(hours, Duration::try_hours, Some(duration_component(minutes, Duration::try_minutes, None)?))?;
+                    Ok(if negative { -offset } else { offset })
+                }
+
+            pub rule lenient_date_time() -> IcalDateTime
+                = date:date() "T" time:raw_time() offset:numeric_offset() {?
+                    NaiveDateTime::new(date, time).checked_sub_signed(offset)
+                        .map(|date_time| IcalDateTime::Utc { date_time: date_time.and_utc() })
+                        .ok_or("Invalid date time")
+                }
+                / date_time()
+
             rule pm_negative() -> bool
                 = "-" { true }
                 / "+" { false }
 
 
             rule duration_seconds() -> Duration
-                = seconds:$(['0'..='9']+) "S" {
-                    Duration::seconds(seconds.parse().unwrap())
+                = seconds:$(['0'..='9']+) "S" {?
+                    duration_component(seconds, Duration::try_seconds, None)
                 }
 
             rule duration_minutes() -> Duration
-                = minutes:$(['0'..='9']+) "M" seconds:duration_seconds()? {
-                    Duration::minutes(minutes.parse().unwrap()) + seconds.unwrap_or(Duration::zero())
+                = minutes:$(['0'..='9']+) "M" seconds:duration_seconds()? {?
+                    duration_component(minutes, Duration::try_minutes, seconds)
                 }
 
             rule duration_hours() -> Duration
-                = hours:$(['0'..='9']+) "H" minutes:duration_minutes()? {
-                    Duration::hours(hours.parse().unwrap()) + minutes.unwrap_or(Duration::zero())
+                = hours:$(['0'..='9']+) "H" minutes:duration_minutes()? {?
+                    duration_component(hours, Duration::try_hours, minutes)
                 }
 
             rule duration_time() -> Duration
@@ -75,13 +115,13 @@ peg::parser! {
                 }
 
             rule duration_days() -> Duration
-                = days:$(['0'..='9']+) "D" time:duration_time()? {
-                    Duration::days(days.parse().unwrap()) + time.unwrap_or(Duration::zero())
+                = days:$(['0'..='9']+) "D" time:duration_time()? {?
+                    duration_component(days, Duration::try_days, time)
                 }
 
             rule duration_weeks() -> Duration
-                = weeks:$(['0'..='9']+) "W" {
-                    Duration::weeks(weeks.parse().unwrap())
+                = weeks:$(['0'..='9']+) "W" {?
+                    duration_component(weeks, Duration::try_weeks, None)
                 }
 
             pub rule duration() -> ICalDuration
@@ -93,6 +133,53 @@ peg::parser! {
                 }}
             }
 
+            pub rule lenient_duration() -> ICalDuration
+            = negative:pm_negative()? "P" weeks:duration_weeks() rest:(days:duration_days() / time:duration_time())? {?
+                let duration = weeks.checked_add(&rest.unwrap_or(Duration::zero())).ok_or("duration out of range")?;
+                Ok(ICalDuration{ duration: if negative.unwrap_or(false) {
+                    -duration
+                } else {
+                    duration
+                }})
+            }
+            / duration()
+
+            rule iso8601_seconds() -> Duration
+                = seconds:$(['0'..='9']+) fraction:(['.' | ','] digits:$(['0'..='9']+) { digits })? "S" {?
+                    let nanos = match fraction {
+                        Some(digits) => {
+                            let digits = &digits[..digits.len().min(9)];
+                            number::<i64>(digits)? * 10i64.pow(9 - digits.len() as u32)
+                        },
+                        None => 0,
+                    };
+                    duration_component(seconds, Duration::try_seconds, Some(Duration::nanoseconds(nanos)))
+                }
+
+            rule iso8601_time() -> Duration
+                = "T" hours:(hours:$(['0'..='9']+) "H" { hours })? minutes:(minutes:$(['0'..='9']+) "M" { minutes })? seconds:iso8601_seconds()? {?
+                    if hours.is_none() && minutes.is_none() && seconds.is_none() {
+                        Err("empty time")
+                    } else {
+                        let minutes = duration_component(minutes.unwrap_or("0"), Duration::try_minutes, seconds)?;
+                        duration_component(hours.unwrap_or("0"), Duration::try_hours, Some(minutes))
+                    }
+                }
+
+            rule iso8601_days() -> Duration
+                = days:$(['0'..='9']+) "D" time:iso8601_time()? {?
+                    duration_component(days, Duration::try_days, time)
+                }
+
+            pub rule iso8601_duration() -> ICalDuration
+            = negative:pm_negative()? "P" duration:(days:iso8601_days() / time:iso8601_time() / weeks:duration_weeks()) {
+                ICalDuration{ duration: if negative.unwrap_or(false) {
+                    -duration
+                } else {
+                    duration
+                }}
+            }
+
         pub rule period() -> IcalPeriod
             = start:date_time() "/" end:date_time() {
                 IcalPeriod::StartEnd {
@@ -103,42 +190,37 @@ peg::parser! {
             / start:date_time() "/" duration:duration() {
                 IcalPeriod::StartDuration {
                     start,
-                    duration: duration
+                    duration
                 }
             }
 
             pub rule utc_offset() -> IcalUTCOffset
-            = negative:pm_negative() hours:$(['0'..='9']{2}) minutes:$(['0'..='9']{2}) seconds:$(['0'..='9']{2})? {
-                let offset = Duration::hours(hours.parse().unwrap()) + Duration::minutes(minutes.parse().unwrap()) + Duration::seconds(seconds.unwrap_or("0").parse().unwrap());
-                IcalUTCOffset{ offset: if negative {
+            = negative:pm_negative() hours:$(['0'..='9']*<2>) minutes:$(['0'..='9']*<2>) seconds:$(['0'..='9']*<2>)? {?
+                let seconds = duration_component(seconds.unwrap_or("0"), Duration::try_seconds, None)?;
+                let offset = duration_component(hours, Duration::try_hours, Some(duration_component(minutes, Duration::try_minutes, Some(seconds))?))?;
+                Ok(IcalUTCOffset{ offset: if negative {
                     -offset
                 } else {
                     offset
-                }}
+                }})
             }
 
             rule recur_frequency_t() -> ICalRecurFrequency
-                = "SECONDLY" { ICalRecurFrequency::Secondly }
-                / "MINUTELY" { ICalRecurFrequency::Minutely }
-                / "HOURLY" { ICalRecurFrequency::Hourly }
-                / "DAILY" { ICalRecurFrequency::Daily }
-                / "WEEKLY" { ICalRecurFrequency::Weekly }
-                / "MONTHLY" { ICalRecurFrequency::Monthly }
-                / "YEARLY" { ICalRecurFrequency::Yearly }
+                = i("SECONDLY") { ICalRecurFrequency::Secondly }
+                / i("MINUTELY") { ICalRecurFrequency::Minutely }
+                / i("HOURLY") { ICalRecurFrequency::Hourly }
+                / i("DAILY") { ICalRecurFrequency::Daily }
+                / i("WEEKLY") { ICalRecurFrequency::Weekly }
+                / i("MONTHLY") { ICalRecurFrequency::Monthly }
+                / i("YEARLY") { ICalRecurFrequency::Yearly }
 
             rule recur_frequency() -> ICalRecurFrequency
-                = "FREQ=" freq:recur_frequency_t() {
+                = i("FREQ=") freq:recur_frequency_t() {
                     freq
                 }
 
             rule recur_until() -> IcalRecurBuilder
-                = ";UNTIL=" date:date() {
-                    IcalRecurBuilder {
-                        limit: Some(IcalRecurLimit::Until(IcalRecurUntil::Date(date))),
-                        ..Default::default()
-                    }
-                }
-                / ";UNTIL=" date_time:date_time() {
+                = i(";UNTIL=") date_time:date_time() {
                     ? match date_time {
                         IcalDateTime::Utc { date_time } => Ok(IcalRecurBuilder {
                             limit: Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(date_time))),
@@ -147,36 +229,43 @@ peg::parser! {
                         _ => Err("Recur until must be in UTC")
                     }
                 }
-
-            rule recur_count() -> IcalRecurBuilder
-                = ";COUNT=" count:$(['0'..='9']+) {
+                / i(";UNTIL=") date:date() {
                     IcalRecurBuilder {
-                        limit: Some(IcalRecurLimit::Count(count.parse().unwrap())),
+                        limit: Some(IcalRecurLimit::Until(IcalRecurUntil::Date(date))),
                         ..Default::default()
                     }
                 }
 
+            rule recur_count() -> IcalRecurBuilder
+                = i(";COUNT=") count:$(['0'..='9']+) {?
+                    let count = count.parse().ok().filter(|count| *count <= MAX_RECUR_COUNT).ok_or("count out of range")?;
+                    Ok(IcalRecurBuilder {
+                        limit: Some(IcalRecurLimit::Count(count)),
+                        ..Default::default()
+                    })
+                }
+
             rule recur_interval() -> IcalRecurBuilder
-                = ";INTERVAL=" interval:$(['0'..='9']+) {
-                    IcalRecurBuilder {
-                        interval: Some(interval.parse().unwrap()),
+                = i(";INTERVAL=") interval:$(['0'..='9']+) {?
+                    Ok(IcalRecurBuilder {
+                        interval: Some(interval.parse().map_err(|_| "interval out of range")?),
                         ..Default::default()
-                    }
+                    })
                 }
 
             rule recur_u8_list() -> Vec<u8>
-                = data:(($(['0'..='9']*<1,2>) ++ ",")) {
-                    data.iter().map(|s| s.parse().unwrap()).collect()
+                = data:(($(['0'..='9']*<1,2>) ++ ",")) {?
+                    data.iter().map(|s| s.parse().map_err(|_| "value out of range")).collect()
                 }
 
             rule two_digit_i8() -> i8
-                = negative:pm_negative()? input:$(['0'..='9']*<1,2>) { 
-                    i8::from_str_radix(input, 10).unwrap() * if negative.unwrap_or(false) { -1 } else { 1 }
+                = negative:pm_negative()? input:$(['0'..='9']*<1,2>) {? 
+                    input.parse::<i8>().ok().and_then(|value| if negative.unwrap_or(false) { value.checked_neg() } else { Some(value) }).ok_or("value out of range")
                 }
 
             rule three_digit_i16() -> i16
-                = negative:pm_negative()? input:$(['0'..='9']*<1,3>) { 
-                    i16::from_str_radix(input, 10).unwrap() * if negative.unwrap_or(false) { -1 } else { 1 }
+                = negative:pm_negative()? input:$(['0'..='9']*<1,3>) {? 
+                    input.parse::<i16>().ok().and_then(|value| if negative.unwrap_or(false) { value.checked_neg() } else { Some(value) }).ok_or("value out of range")
                 }
 
             rule recur_i8_list() -> Vec<i8>
@@ -190,17 +279,17 @@ peg::parser! {
                 }
 
             rule recur_day_of_week() -> ICalRecurDayOfWeek
-                = "SU" { ICalRecurDayOfWeek::Sunday }
-                / "MO" { ICalRecurDayOfWeek::Monday }
-                / "TU" { ICalRecurDayOfWeek::Tuesday }
-                / "WE" { ICalRecurDayOfWeek::Wednesday }
-                / "TH" { ICalRecurDayOfWeek::Thursday }
-                / "FR" { ICalRecurDayOfWeek::Friday }
-                / "SA" { ICalRecurDayOfWeek::Saturday }
+                = i("SU") { ICalRecurDayOfWeek::Sunday }
+                / i("MO") { ICalRecurDayOfWeek::Monday }
+                / i("TU") { ICalRecurDayOfWeek::Tuesday }
+                / i("WE") { ICalRecurDayOfWeek::Wednesday }
+                / i("TH") { ICalRecurDayOfWeek::Thursday }
+                / i("FR") { ICalRecurDayOfWeek::Friday }
+                / i("SA") { ICalRecurDayOfWeek::Saturday }
             
             
             rule recur_by_second() -> IcalRecurBuilder
-                = ";BYSECOND=" seconds:recur_u8_list() {
+                = i(";BYSECOND=") seconds:recur_u8_list() {
                     IcalRecurBuilder {
                         by_second: Some(seconds),
                         ..Default::default()
@@ -208,7 +297,7 @@ peg::parser! {
                 }
             
             rule recur_by_minute() -> IcalRecurBuilder
-                = ";BYMINUTE=" minutes:recur_u8_list() {
+                = i(";BYMINUTE=") minutes:recur_u8_list() {
                     IcalRecurBuilder {
                         by_minute: Some(minutes),
                         ..Default::default()
@@ -216,7 +305,7 @@ peg::parser! {
                 }
 
             rule recur_by_hour() -> IcalRecurBuilder
-                = ";BYHOUR=" hours:recur_u8_list() {
+                = i(";BYHOUR=") hours:recur_u8_list() {
                     IcalRecurBuilder {
                         by_hour: Some(hours),
                         ..Default::default()
@@ -237,7 +326,7 @@ peg::parser! {
                 }
 
             rule recur_by_day() -> IcalRecurBuilder
-                = ";BYDAY=" days:recur_by_day_list() {
+                = i(";BYDAY=") days:recur_by_day_list() {
                     IcalRecurBuilder {
                         by_day: Some(days),
                         ..Default::default()
@@ -245,7 +334,7 @@ peg::parser! {
                 }
 
             rule recur_by_month_day() -> IcalRecurBuilder
-                = ";BYMONTHDAY=" days:recur_i8_list() {
+                = i(";BYMONTHDAY=") days:recur_i8_list() {
                     IcalRecurBuilder {
                         by_month_day: Some(days),
                         ..Default::default()
@@ -253,7 +342,7 @@ peg::parser! {
                 }
 
             rule recur_by_year_day() -> IcalRecurBuilder
-                = ";BYYEARDAY=" days:recur_i16_list() {
+                = i(";BYYEARDAY=") days:recur_i16_list() {
                     IcalRecurBuilder {
                         by_year_day: Some(days),
                         ..Default::default()
@@ -261,7 +350,7 @@ peg::parser! {
                 }
 
             rule recur_by_week_no() -> IcalRecurBuilder
-                = ";BYWEEKNO=" weeks:recur_i8_list() {
+                = i(";BYWEEKNO=") weeks:recur_i8_list() {
                     IcalRecurBuilder {
                         by_week_no: Some(weeks),
                         ..Default::default()
@@ -269,7 +358,7 @@ peg::parser! {
                 }
 
             rule recur_by_month() -> IcalRecurBuilder
-                = ";BYMONTH=" months:recur_u8_list() {
+                = i(";BYMONTH=") months:recur_u8_list() {
                     IcalRecurBuilder {
                         by_month: Some(months),
                         ..Default::default()
@@ -277,7 +366,7 @@ peg::parser! {
                 }
 
             rule recur_by_set_pos() -> IcalRecurBuilder
-                = ";BYSETPOS=" pos:recur_i16_list() {
+                = i(";BYSETPOS=") pos:recur_i16_list() {
                     IcalRecurBuilder {
                         by_set_pos: Some(pos),
                         ..Default::default()
@@ -285,7 +374,7 @@ peg::parser! {
                 }
 
             rule recur_wkst() -> IcalRecurBuilder
-                = ";WKST=" weekday:recur_day_of_week() {
+                = i(";WKST=") weekday:recur_day_of_week() {
                     IcalRecurBuilder {
                         wkst: Some(weekday),
                         ..Default::default()
@@ -311,10 +400,108 @@ peg::parser! {
     }
 }
 
+pub type IcalParameters = HashMap<String, Vec<String>>;
+
+/// Vendor `X-` properties keyed by name. Properties sharing a name keep the order they appeared in.
+pub type IcalExtensions = HashMap<String, Vec<Property>>;
+
+pub(crate) fn is_extension(property: &Property) -> bool {
+    property.name.len() > 2 && property.name.get(..2).is_some_and(|prefix| prefix.eq_ignore_ascii_case("X-"))
+}
+
+/// A property with one value for each of `params`, for serializing.
+pub(crate) fn new_property(name: &str, params: &[(&str, &str)], value: String) -> Property {
+    let params = params.iter().map(|(name, value)| (name.to_string(), vec![value.to_string()])).collect::<Vec<_>>();
+    Property { name: name.to_string(), params: (!params.is_empty()).then_some(params), value: Some(value) }
+}
+
+/// Writes a property as a content line ending in CRLF, folded so no line exceeds 75 octets. Parameter values
+/// containing `:`, `;` or `,` are quoted.
+pub(crate) fn content_line(property: &Property) -> String {
+    let mut line = property.name.clone();
+    for (name, values) in property.params.iter().flatten() {
+        let values = values.iter().map(|value| {
+            let value = value.replace('"', "");
+            if value.contains([':', ';', ',']) { format!("\"{value}\"") } else { value }
+        }).collect::<Vec<_>>();
+        line.push_str(&format!(";{name}={}", values.join(",")));
+    }
+    line.push(':');
+    line.push_str(property.value.as_deref().unwrap_or_default());
+
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3 + 2);
+    let mut width = 0;
+    for c in line.chars() {
+        // Readers trim trailing whitespace, so a space never ends a folded line.
+        if width + c.len_utf8() > 75 || (width + c.len_utf8() == 75 && (c == ' ' || c == '\t')) {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Raw components whose properties can be looked up by name, for properties without a typed wrapper.
+pub trait HasProperties {
+    fn properties(&self) -> &[Property];
+}
+
+macro_rules! has_properties {
+    ($($component:ty),*) => {
+        $(impl HasProperties for $component {
+            fn properties(&self) -> &[Property] {
+                &self.properties
+            }
+        })*
+    };
+}
+
+has_properties!(IcalCalendar, IcalEvent, IcalTodo, IcalJournal, IcalFreeBusy, IcalAlarm, IcalTimeZone, IcalTimeZoneTransition);
+
+/// The first property called `name`, compared case-insensitively.
+pub fn property<'a, C: HasProperties>(component: &'a C, name: &str) -> Option<&'a Property> {
+    component.properties().iter().find(|property| property.name.eq_ignore_ascii_case(name))
+}
+
+/// Every property called `name`, in order, for properties that may repeat.
+pub fn properties<'a, C: HasProperties>(component: &'a C, name: &'a str) -> impl Iterator<Item = &'a Property> {
+    component.properties().iter().filter(move |property| property.name.eq_ignore_ascii_case(name))
+}
+
+/// A parsed value together with every parameter of the property it came from.
+pub struct Parameterized<T> {
+    pub value: T,
+    pub params: IcalParameters,
+}
+
+impl<T> Parameterized<T> {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)?.first().map(|value| value.as_str())
+    }
+}
+
+impl<T: TryFrom<Property, Error = Error>> TryFrom<Property> for Parameterized<T> {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<Self, Self::Error>{
+        let params = property.params.clone().map(|params| params.into_iter().collect()).unwrap_or_default();
+        Ok(Parameterized { value: T::try_from(property)?, params })
+    }
+}
+
 pub struct ICalBinary {
     pub data: Vec<u8>,
 }
 
+impl ICalBinary {
+    /// The data in base64, for a property with `ENCODING=BASE64`.
+    pub fn to_ical(&self) -> String {
+        STANDARD.encode(&self.data)
+    }
+}
+
 impl TryFrom<Property> for ICalBinary {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
@@ -346,6 +533,12 @@ impl TryFrom<Property> for ICalBoolean {
     }
 }
 
+impl fmt::Display for ICalBoolean {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.value { "TRUE" } else { "FALSE" })
+    }
+}
+
 pub struct ICalCalAddress {
     pub address: Uri,
 }
@@ -365,30 +558,75 @@ impl TryFrom<Property> for ICalCalAddress {
     }
 }
 
-
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IcalDate {
     pub date: NaiveDate,
 }
 
+impl IcalDate {
+    pub fn to_ical(&self) -> String {
+        self.date.format("%Y%m%d").to_string()
+    }
+}
+
+/// Value of an all-digit field, or `None` if any byte isn't a digit.
+fn fast_digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |value, byte| byte.is_ascii_digit().then(|| value * 10 + u32::from(byte - b'0')))
+}
+
+fn fast_date(bytes: &[u8]) -> Option<NaiveDate> {
+    if bytes.len() != 8 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(fast_digits(&bytes[..4])? as i32, fast_digits(&bytes[4..6])?, fast_digits(&bytes[6..])?)
+}
+
+/// Fixed-width `YYYYMMDDTHHMMSS[Z]` without the grammar. Anything else, including invalid dates, returns `None` and
+/// is left to the grammar, so errors are reported the same way.
+fn fast_date_time(bytes: &[u8]) -> Option<IcalDateTime> {
+    let utc = match bytes.len() {
+        15 => false,
+        16 if bytes[15] == b'Z' => true,
+        _ => return None,
+    };
+    if bytes[8] != b'T' {
+        return None;
+    }
+
+    let time = NaiveTime::from_hms_opt(fast_digits(&bytes[9..11])?, fast_digits(&bytes[11..13])?, fast_digits(&bytes[13..15])?)?;
+    let date_time = fast_date(&bytes[..8])?.and_time(time);
+    Some(if utc { IcalDateTime::Utc { date_time: date_time.and_utc() } } else { IcalDateTime::Floating { date_time } })
+}
+
+impl FromStr for IcalDate {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(date) = fast_date(value.as_bytes()) {
+            return Ok(IcalDate { date });
+        }
+        ical_type_parser::date(value).map(|date| IcalDate { date }).map_err(|_| Error::TypeDecode(ICalTypes::Date))
+    }
+}
+
 impl TryFrom<Property> for IcalDate {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match property.value {
-            Some(value) => {
-                match ical_type_parser::date(&value) {
-                    Ok(date) => Ok(IcalDate { date }),
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::Date))
-                }
-            },
+            Some(value) => value.parse(),
             None => Err(Error::TypeDecode(ICalTypes::Date))
         }
     }
 }
 
+pub(crate) fn get_param<'a>(property: &'a Property, name: &str) -> Option<&'a String> {
+    property.params.as_ref()?.iter().find(|param| param.0 == name)?.1.first()
+}
+
 fn get_tzid(property: &Property) -> Option<String> {
-    property.params.as_ref()?.iter().find(|param| param.0 == "TZID").map(|param| param.1.first()).unwrap().cloned()
+    get_param(property, "TZID").cloned()
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum IcalDateTime {
     Utc {
         date_time: DateTime<Utc>,
@@ -402,43 +640,189 @@ pub enum IcalDateTime {
     },
 }
 
+/// Parses a bare value, which is either UTC or floating. A TZID can only come from a property parameter.
+impl FromStr for IcalDateTime {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(date_time) = fast_date_time(value.as_bytes()) {
+            return Ok(date_time);
+        }
+        ical_type_parser::date_time(value).map_err(|_| Error::TypeDecode(ICalTypes::DateTime))
+    }
+}
+
+impl IcalDateTime {
+    /// Writes the value without its TZID, which belongs in the property's parameters.
+    pub fn to_ical(&self) -> String {
+        match self {
+            IcalDateTime::Utc { date_time } => date_time.format("%Y%m%dT%H%M%SZ").to_string(),
+            IcalDateTime::Floating { date_time } | IcalDateTime::TimeZone { date_time, .. } => date_time.format("%Y%m%dT%H%M%S").to_string(),
+        }
+    }
+
+    fn map_local(&self, f: impl FnOnce(NaiveDateTime) -> Option<NaiveDateTime>) -> Option<Self> {
+        Some(match self {
+            IcalDateTime::Utc { date_time } => IcalDateTime::Utc { date_time: f(date_time.naive_utc())?.and_utc() },
+            IcalDateTime::Floating { date_time } => IcalDateTime::Floating { date_time: f(*date_time)? },
+            IcalDateTime::TimeZone { date_time, tzid } => IcalDateTime::TimeZone { date_time: f(*date_time)?, tzid: tzid.clone() },
+        })
+    }
+
+    /// Adds a DURATION, keeping the variant and any TZID. Zoned values move in local time, before any conversion to
+    /// UTC. Returns `None` if the result is out of range.
+    pub fn checked_add_duration(&self, duration: &ICalDuration) -> Option<Self> {
+        self.map_local(|date_time| date_time.checked_add_signed(duration.duration))
+    }
+
+    /// Like `checked_add_duration`, but subtracts.
+    pub fn checked_sub_duration(&self, duration: &ICalDuration) -> Option<Self> {
+        self.map_local(|date_time| date_time.checked_sub_signed(duration.duration))
+    }
+
+    /// Panics if the result is out of range, like adding to a chrono date-time.
+    pub fn add_duration(&self, duration: &ICalDuration) -> Self {
+        self.checked_add_duration(duration).expect("date-time out of range")
+    }
+
+    /// Panics if the result is out of range, like subtracting from a chrono date-time.
+    pub fn sub_duration(&self, duration: &ICalDuration) -> Self {
+        self.checked_sub_duration(duration).expect("date-time out of range")
+    }
+
+    /// Applies the property's TZID. A UTC value (trailing `Z`) with a TZID contradicts itself and is rejected.
+    fn with_tzid(self, property: &Property) -> Result<Self, Error> {
+        match (self, get_tzid(property)) {
+            (IcalDateTime::Utc { date_time }, None) => Ok(IcalDateTime::Utc { date_time }),
+            (IcalDateTime::Utc { .. }, Some(_)) => Err(Error::TypeDecode(ICalTypes::DateTime)),
+            (IcalDateTime::Floating { date_time }, None) => Ok(IcalDateTime::Floating { date_time }),
+            (IcalDateTime::Floating { date_time }, Some(tzid)) => Ok(IcalDateTime::TimeZone { date_time, tzid }),
+            // The grammar never produces a zoned value, only the property parameter does.
+            (IcalDateTime::TimeZone { .. }, _) => Err(Error::TypeDecode(ICalTypes::DateTime)),
+        }
+    }
+
+    /// Parses a comma-separated DATE-TIME list, as used by RDATE and EXDATE, applying the property's TZID to each.
+    pub fn list_from(property: &Property) -> Result<Vec<Self>, Error> {
+        match &property.value {
+            Some(value) => value.split(',').map(|value| value.parse::<IcalDateTime>()?.with_tzid(property)).collect(),
+            None => Err(Error::TypeDecode(ICalTypes::DateTime))
+        }
+    }
+
+    /// Like `try_from`, but also accepts an ISO 8601 numeric offset in place of `Z` (`+0000`, `-05:00`) and
+    /// normalizes it to UTC. RFC 5545 has no such form, so this is only used when parsing leniently.
+    pub fn try_from_lenient(property: Property) -> Result<Self, Error> {
+        match &property.value {
+            Some(value) => ical_type_parser::lenient_date_time(value).map_err(|_| Error::TypeDecode(ICalTypes::DateTime))?.with_tzid(&property),
+            None => Err(Error::TypeDecode(ICalTypes::DateTime))
+        }
+    }
+}
+
 impl TryFrom<Property> for IcalDateTime {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match &property.value {
-            Some(value) => {
-                let tzid = get_tzid(&property);
-                match ical_type_parser::date_time(&value) {
-                    Ok(date_time) => {
-                        match (date_time, tzid) {
-                            (IcalDateTime::Utc { date_time }, None) => Ok(IcalDateTime::Utc { date_time }),
-                            (IcalDateTime::Floating { date_time }, None) => Ok(IcalDateTime::Floating { date_time }),
-                            (IcalDateTime::Floating { date_time }, Some(tzid)) => Ok(IcalDateTime::TimeZone { date_time, tzid }),
-                            _ => Err(Error::TypeDecode(ICalTypes::DateTime))
-                        }
-                    },
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::DateTime))
-                }
-            },
+            Some(value) => value.parse::<IcalDateTime>()?.with_tzid(&property),
             None => Err(Error::TypeDecode(ICalTypes::DateTime))
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ICalDuration {
     pub duration: Duration,
 }
 
+impl FromStr for ICalDuration {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        ical_type_parser::duration(value).map_err(|_| Error::TypeDecode(ICalTypes::Duration))
+    }
+}
+
 impl TryFrom<Property> for ICalDuration {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match property.value {
-            Some(value) => {
-                match ical_type_parser::duration(&value) {
-                    Ok(duration) => Ok(duration),
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::Duration))
-                }
-            },
+            Some(value) => value.parse(),
+            None => Err(Error::TypeDecode(ICalTypes::Duration))
+        }
+    }
+}
+
+impl ICalDuration {
+    /// Writes the duration in whole weeks when it is one, otherwise as days and time. Fractional seconds are dropped.
+    pub fn to_ical(&self) -> String {
+        let sign = if self.duration < Duration::zero() { "-" } else { "" };
+        let seconds = self.duration.num_seconds().unsigned_abs();
+        let (days, hours, minutes, seconds) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+        if days > 0 && days % 7 == 0 && hours == 0 && minutes == 0 && seconds == 0 {
+            return format!("{sign}P{}W", days / 7);
+        }
+
+        let mut value = format!("{sign}P");
+        if days > 0 {
+            value.push_str(&format!("{days}D"));
+        }
+        if days == 0 || hours > 0 || minutes > 0 || seconds > 0 {
+            value.push('T');
+            // Each time component needs the larger ones before it, so `T1H0M5S` rather than `T1H5S`.
+            if hours > 0 {
+                value.push_str(&format!("{hours}H"));
+            }
+            if (hours > 0 && seconds > 0) || minutes > 0 {
+                value.push_str(&format!("{minutes}M"));
+            }
+            if seconds > 0 || (hours == 0 && minutes == 0) {
+                value.push_str(&format!("{seconds}S"));
+            }
+        }
+        value
+    }
+
+    /// Writes an ISO 8601 duration for systems outside iCalendar. Unlike `to_ical`, weeks are always written as
+    /// days, components in between may be skipped (`PT1H5S`) and fractional seconds are kept.
+    pub fn to_iso8601(&self) -> String {
+        let sign = if self.duration < Duration::zero() { "-" } else { "" };
+        let total = self.duration.abs();
+        let seconds = total.num_seconds().unsigned_abs();
+        let nanos = (total - Duration::seconds(total.num_seconds())).num_nanoseconds().unwrap_or(0);
+        let (days, hours, minutes, seconds) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+        let mut value = format!("{sign}P");
+        if days > 0 {
+            value.push_str(&format!("{days}D"));
+        }
+        if hours > 0 || minutes > 0 || seconds > 0 || nanos > 0 || days == 0 {
+            value.push('T');
+            if hours > 0 {
+                value.push_str(&format!("{hours}H"));
+            }
+            if minutes > 0 {
+                value.push_str(&format!("{minutes}M"));
+            }
+            if nanos > 0 {
+                value.push_str(&format!("{seconds}.{}S", format!("{nanos:09}").trim_end_matches('0')));
+            } else if seconds > 0 || (hours == 0 && minutes == 0) {
+                value.push_str(&format!("{seconds}S"));
+            }
+        }
+        value
+    }
+
+    /// Parses an ISO 8601 duration such as `P1DT2H` or `PT1.5S`. Years and months have no fixed length and are
+    /// rejected, as iCalendar does; weeks may not be combined with other components.
+    pub fn from_iso8601(value: &str) -> Result<Self, Error> {
+        ical_type_parser::iso8601_duration(value).map_err(|_| Error::TypeDecode(ICalTypes::Duration))
+    }
+
+    /// Like `try_from`, but also accepts weeks followed by days or time (`P1W2D`), summing the components. RFC 5545
+    /// forbids mixing weeks with anything else, so this is only used when parsing leniently.
+    pub fn try_from_lenient(property: Property) -> Result<Self, Error> {
+        match property.value {
+            Some(value) => ical_type_parser::lenient_duration(&value).map_err(|_| Error::TypeDecode(ICalTypes::Duration)),
             None => Err(Error::TypeDecode(ICalTypes::Duration))
         }
     }
@@ -463,6 +847,13 @@ impl TryFrom<Property> for IcalFloat {
     }
 }
 
+impl fmt::Display for IcalFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IcalInteger {
     pub value: i32,
 }
@@ -482,6 +873,13 @@ impl TryFrom<Property> for IcalInteger {
     }
 }
 
+impl fmt::Display for IcalInteger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum IcalPeriod {
     StartEnd {
         start: IcalDateTime,
@@ -493,22 +891,53 @@ pub enum IcalPeriod {
     },
 }
 
+impl IcalPeriod {
+    pub fn to_ical(&self) -> String {
+        match self {
+            IcalPeriod::StartEnd { start, end } => format!("{}/{}", start.to_ical(), end.to_ical()),
+            IcalPeriod::StartDuration { start, duration } => format!("{}/{}", start.to_ical(), duration.to_ical()),
+        }
+    }
+
+    /// The period as absolute UTC instants. TZIDs are resolved like an event's, a duration is added in local time
+    /// before converting, and floating times are treated as UTC.
+    pub fn to_utc_range(&self, timezone_map: &TimezoneMap) -> Result<(DateTime<Utc>, DateTime<Utc>), Error> {
+        let to_utc = |date_time: &IcalDateTime| match date_time {
+            IcalDateTime::Utc { date_time } => Ok(*date_time),
+            IcalDateTime::Floating { date_time } => Ok(date_time.and_utc()),
+            IcalDateTime::TimeZone { date_time, tzid } => zoned_to_utc(*date_time, tzid, timezone_map),
+        };
+        match self {
+            IcalPeriod::StartEnd { start, end } => Ok((to_utc(start)?, to_utc(end)?)),
+            IcalPeriod::StartDuration { start, duration } => {
+                let end = start.checked_add_duration(duration).ok_or(Error::InvalidDateTime)?;
+                Ok((to_utc(start)?, to_utc(&end)?))
+            },
+        }
+    }
+}
+
+/// Parses a bare value whose endpoints are UTC or floating.
+impl FromStr for IcalPeriod {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        ical_type_parser::period(value).map_err(|_| Error::TypeDecode(ICalTypes::Period))
+    }
+}
+
 impl TryFrom<Property> for IcalPeriod {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match property.value {
             Some(value) => {
-                let parts: Vec<&str> = value.split('/').collect();
-                if parts.len() == 2 {
-                    let start = IcalDateTime::try_from(Property { name: property.name.clone(), value: Some(parts[0].to_string()), params: property.params.clone() })?;
-                    let end = IcalDateTime::try_from(Property { name: property.name.clone(), value: Some(parts[1].to_string()), params: property.params.clone() })?;
-                    Ok(IcalPeriod::StartEnd { start, end })
-                } else if parts.len() == 1 {
-                    let start = IcalDateTime::try_from(Property { name: property.name.clone(), value: Some(parts[0].to_string()), params: property.params.clone() })?;
-                    let duration = ICalDuration::try_from(Property { name: property.name.clone(), value: Some(parts[1].to_string()), params: property.params.clone() })?;
+                let (start, end) = value.split_once('/').ok_or(Error::TypeDecode(ICalTypes::Period))?;
+                let start = IcalDateTime::try_from(Property { name: property.name.clone(), value: Some(start.to_string()), params: property.params.clone() })?;
+                if end.starts_with(['P', '+', '-']) {
+                    let duration = ICalDuration::try_from(Property { name: property.name.clone(), value: Some(end.to_string()), params: property.params.clone() })?;
                     Ok(IcalPeriod::StartDuration { start, duration })
                 } else {
-                    Err(Error::TypeDecode(ICalTypes::Period))
+                    let end = IcalDateTime::try_from(Property { name: property.name.clone(), value: Some(end.to_string()), params: property.params.clone() })?;
+                    Ok(IcalPeriod::StartEnd { start, end })
                 }
             },
             None => Err(Error::TypeDecode(ICalTypes::Period))
@@ -516,16 +945,19 @@ impl TryFrom<Property> for IcalPeriod {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IcalRecurUntil {
     Date(NaiveDate),
     DateTime(DateTime<Utc>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IcalRecurLimit {
     Count(u64),
     Until(IcalRecurUntil),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ICalRecurFrequency {
     Secondly,
     Minutely,
@@ -536,6 +968,7 @@ pub enum ICalRecurFrequency {
     Yearly,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ICalRecurDayOfWeek {
     Sunday,
     Monday,
@@ -546,6 +979,7 @@ pub enum ICalRecurDayOfWeek {
     Saturday,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct IcalRecurWeekDay {
     pub day: ICalRecurDayOfWeek,
     pub nth_of_month: Option<i8>,
@@ -665,41 +1099,276 @@ impl IcalRecurBuilder {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IcalRecur {
-    frequency: ICalRecurFrequency,
-    limit: Option<IcalRecurLimit>,
-    interval: Option<u64>,
-    by_second: Option<Vec<u8>>,
-    by_minute: Option<Vec<u8>>,
-    by_hour: Option<Vec<u8>>,
-    by_day: Option<Vec<IcalRecurWeekDay>>,
-    by_month_day: Option<Vec<i8>>,
-    by_year_day: Option<Vec<i16>>,
-    by_week_no: Option<Vec<i8>>,
-    by_month: Option<Vec<u8>>,
-    by_set_pos: Option<Vec<i16>>,
-    wkst: Option<ICalRecurDayOfWeek>,
+    pub frequency: ICalRecurFrequency,
+    pub limit: Option<IcalRecurLimit>,
+    pub interval: Option<u64>,
+    pub by_second: Option<Vec<u8>>,
+    pub by_minute: Option<Vec<u8>>,
+    pub by_hour: Option<Vec<u8>>,
+    pub by_day: Option<Vec<IcalRecurWeekDay>>,
+    pub by_month_day: Option<Vec<i8>>,
+    pub by_year_day: Option<Vec<i16>>,
+    pub by_week_no: Option<Vec<i8>>,
+    pub by_month: Option<Vec<u8>>,
+    pub by_set_pos: Option<Vec<i16>>,
+    pub wkst: Option<ICalRecurDayOfWeek>,
+}
+
+fn valid_second(value: i32) -> bool { (0..=60).contains(&value) }
+fn valid_minute(value: i32) -> bool { (0..=59).contains(&value) }
+fn valid_hour(value: i32) -> bool { (0..=23).contains(&value) }
+fn valid_month(value: i32) -> bool { (1..=12).contains(&value) }
+fn valid_month_day(value: i32) -> bool { (1..=31).contains(&value.abs()) }
+fn valid_year_day(value: i32) -> bool { (1..=366).contains(&value.abs()) }
+fn valid_week_no(value: i32) -> bool { (1..=53).contains(&value.abs()) }
+
+fn normalize_list<T: Ord>(values: &mut Option<Vec<T>>) {
+    if let Some(values) = values {
+        values.sort_unstable();
+        values.dedup();
+    }
+}
+
+fn all_valid<T: Copy + Into<i32>>(values: &Option<Vec<T>>, valid: fn(i32) -> bool) -> bool {
+    values.iter().flatten().all(|value| valid((*value).into()))
+}
+
+fn retain_valid<T: Copy + Into<i32>>(values: &mut Option<Vec<T>>, valid: fn(i32) -> bool) -> bool {
+    match values {
+        Some(values) => {
+            values.retain(|value| valid((*value).into()));
+            !values.is_empty()
+        },
+        None => true
+    }
+}
+
+impl IcalRecur {
+    /// Sorts and de-duplicates every BY* list, so rules that differ only in the order or repetition of those
+    /// values compare equal. Parsing already does this.
+    pub fn normalize(&mut self) {
+        normalize_list(&mut self.by_second);
+        normalize_list(&mut self.by_minute);
+        normalize_list(&mut self.by_hour);
+        normalize_list(&mut self.by_day);
+        normalize_list(&mut self.by_month_day);
+        normalize_list(&mut self.by_year_day);
+        normalize_list(&mut self.by_week_no);
+        normalize_list(&mut self.by_month);
+        normalize_list(&mut self.by_set_pos);
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let valid = [
+            all_valid(&self.by_second, valid_second),
+            all_valid(&self.by_minute, valid_minute),
+            all_valid(&self.by_hour, valid_hour),
+            self.by_day.iter().flatten().all(|day| day.nth_of_month.is_none_or(|nth| valid_week_no(nth.into()))),
+            all_valid(&self.by_month_day, valid_month_day),
+            all_valid(&self.by_year_day, valid_year_day),
+            all_valid(&self.by_week_no, valid_week_no),
+            all_valid(&self.by_month, valid_month),
+            all_valid(&self.by_set_pos, valid_year_day),
+        ];
+
+        if valid.iter().all(|valid| *valid) {
+            Ok(())
+        } else {
+            Err(Error::TypeDecode(ICalTypes::Recur))
+        }
+    }
+
+    /// Checks UNTIL against DTSTART's value type, which RFC 5545 requires to match. A DATE-TIME UNTIL is always UTC,
+    /// since the grammar rejects any other form.
+    pub fn validate_until(&self, dtstart_is_date: bool) -> Result<(), Error> {
+        match self.limit {
+            Some(IcalRecurLimit::Until(IcalRecurUntil::Date(_))) if !dtstart_is_date => Err(Error::UntilMismatch),
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(_))) if dtstart_is_date => Err(Error::UntilMismatch),
+            _ => Ok(()),
+        }
+    }
+
+    /// Drops out-of-range BY* values, returning false if a list was left empty and the rule can no longer match.
+    pub(crate) fn retain_valid(&mut self) -> bool {
+        if let Some(days) = &mut self.by_day {
+            days.retain(|day| day.nth_of_month.is_none_or(|nth| valid_week_no(nth.into())));
+        }
+
+        let retained = [
+            retain_valid(&mut self.by_second, valid_second),
+            retain_valid(&mut self.by_minute, valid_minute),
+            retain_valid(&mut self.by_hour, valid_hour),
+            self.by_day.as_ref().is_none_or(|days| !days.is_empty()),
+            retain_valid(&mut self.by_month_day, valid_month_day),
+            retain_valid(&mut self.by_year_day, valid_year_day),
+            retain_valid(&mut self.by_week_no, valid_week_no),
+            retain_valid(&mut self.by_month, valid_month),
+            retain_valid(&mut self.by_set_pos, valid_year_day),
+        ];
+
+        retained.iter().all(|retained| *retained)
+    }
+}
+
+impl ICalRecurFrequency {
+    fn as_str(self) -> &'static str {
+        match self {
+            ICalRecurFrequency::Secondly => "SECONDLY",
+            ICalRecurFrequency::Minutely => "MINUTELY",
+            ICalRecurFrequency::Hourly => "HOURLY",
+            ICalRecurFrequency::Daily => "DAILY",
+            ICalRecurFrequency::Weekly => "WEEKLY",
+            ICalRecurFrequency::Monthly => "MONTHLY",
+            ICalRecurFrequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+impl ICalRecurDayOfWeek {
+    fn as_str(self) -> &'static str {
+        match self {
+            ICalRecurDayOfWeek::Sunday => "SU",
+            ICalRecurDayOfWeek::Monday => "MO",
+            ICalRecurDayOfWeek::Tuesday => "TU",
+            ICalRecurDayOfWeek::Wednesday => "WE",
+            ICalRecurDayOfWeek::Thursday => "TH",
+            ICalRecurDayOfWeek::Friday => "FR",
+            ICalRecurDayOfWeek::Saturday => "SA",
+        }
+    }
+}
+
+impl IcalRecur {
+    /// Writes the rule as an RRULE value, with parts in RFC 5545 order.
+    pub fn to_ical(&self) -> String {
+        fn list<T: ToString>(name: &str, values: &Option<Vec<T>>) -> Option<String> {
+            values.as_ref().map(|values| format!("{name}={}", values.iter().map(T::to_string).collect::<Vec<_>>().join(",")))
+        }
+        let by_day = self.by_day.as_ref().map(|days| days.iter().map(|day| format!("{}{}", day.nth_of_month.map(|nth| nth.to_string()).unwrap_or_default(), day.day.as_str())).collect::<Vec<_>>());
+
+        let mut parts = vec![format!("FREQ={}", self.frequency.as_str())];
+        parts.extend(match self.limit {
+            Some(IcalRecurLimit::Until(IcalRecurUntil::Date(date))) => Some(format!("UNTIL={}", date.format("%Y%m%d"))),
+            Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(date_time))) => Some(format!("UNTIL={}", date_time.format("%Y%m%dT%H%M%SZ"))),
+            Some(IcalRecurLimit::Count(count)) => Some(format!("COUNT={count}")),
+            None => None,
+        });
+        parts.extend(self.interval.map(|interval| format!("INTERVAL={interval}")));
+        parts.extend(list("BYSECOND", &self.by_second));
+        parts.extend(list("BYMINUTE", &self.by_minute));
+        parts.extend(list("BYHOUR", &self.by_hour));
+        parts.extend(list("BYDAY", &by_day));
+        parts.extend(list("BYMONTHDAY", &self.by_month_day));
+        parts.extend(list("BYYEARDAY", &self.by_year_day));
+        parts.extend(list("BYWEEKNO", &self.by_week_no));
+        parts.extend(list("BYMONTH", &self.by_month));
+        parts.extend(list("BYSETPOS", &self.by_set_pos));
+        parts.extend(self.wkst.map(|wkst| format!("WKST={}", wkst.as_str())));
+        parts.join(";")
+    }
+}
+
+impl FromStr for IcalRecur {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut recur = ical_type_parser::recur(value).map_err(|_| Error::TypeDecode(ICalTypes::Recur))?;
+        recur.normalize();
+        Ok(recur)
+    }
 }
 
 impl TryFrom<Property> for IcalRecur {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match property.value {
-            Some(value) => {
-                match ical_type_parser::recur(&value) {
-                    Ok(recur) => Ok(recur),
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::Recur))
-                }
-            },
+            Some(value) => value.parse(),
             None => Err(Error::TypeDecode(ICalTypes::Recur))
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IcalText {
+    /// The escaped value as it appeared in the input. See `unescaped` for the text itself.
     pub value: String,
 }
 
+fn unescape_text(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => value.push('\n'),
+            Some(escaped @ ('\\' | ';' | ',')) => value.push(escaped),
+            // Unknown escapes are kept as written.
+            Some(other) => {
+                value.push('\\');
+                value.push(other);
+            },
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+fn escape_text(value: &str) -> String {
+    let mut raw = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => raw.push_str("\\\\"),
+            ';' => raw.push_str("\\;"),
+            ',' => raw.push_str("\\,"),
+            '\n' => raw.push_str("\\n"),
+            _ => raw.push(c),
+        }
+    }
+    raw
+}
+
+impl IcalText {
+    /// Escapes `text` into a TEXT value.
+    pub fn new(text: &str) -> Self {
+        IcalText { value: escape_text(text) }
+    }
+
+    /// The text with its escapes resolved.
+    pub fn unescaped(&self) -> String {
+        unescape_text(&self.value)
+    }
+
+    /// The escaped TEXT value, byte for byte as it was read.
+    pub fn to_ical(&self) -> String {
+        self.value.clone()
+    }
+}
+
+impl Deref for IcalText {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<str> for IcalText {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Shows the unescaped text; use `to_ical` for the escaped form.
+impl fmt::Display for IcalText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.unescaped())
+    }
+}
+
 impl TryFrom<Property> for IcalText {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
@@ -712,6 +1381,97 @@ impl TryFrom<Property> for IcalText {
     }
 }
 
+/// A TEXT value borrowed from the property it was read from. The unescaped text only allocates when the input
+/// actually contains escapes; use `IcalText` when an owned value is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcalTextRef<'a> {
+    /// The escaped value as it appeared in the input.
+    pub value: &'a str,
+}
+
+impl<'a> IcalTextRef<'a> {
+    pub fn new(value: &'a str) -> Self {
+        IcalTextRef { value }
+    }
+
+    /// The text with its escapes resolved, borrowed when there are none.
+    pub fn unescaped(&self) -> Cow<'a, str> {
+        if self.value.contains('\\') { Cow::Owned(unescape_text(self.value)) } else { Cow::Borrowed(self.value) }
+    }
+
+    pub fn into_owned(self) -> IcalText {
+        IcalText { value: self.value.to_string() }
+    }
+}
+
+impl<'a> TryFrom<&'a Property> for IcalTextRef<'a> {
+    type Error = Error;
+    fn try_from(property: &'a Property) -> Result<Self, Self::Error>{
+        match &property.value {
+            Some(raw) => Ok(IcalTextRef::new(raw)),
+            None => Err(Error::TypeDecode(ICalTypes::Text))
+        }
+    }
+}
+
+impl From<IcalTextRef<'_>> for IcalText {
+    fn from(value: IcalTextRef<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+/// A REQUEST-STATUS value: a dotted status code such as `2.0`, its description and, optionally, the data it
+/// refers to. The text parts are unescaped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcalRequestStatus {
+    pub code: String,
+    pub description: String,
+    pub extra: Option<String>,
+}
+
+/// Splits on `separator` where it isn't escaped with a backslash, into at most `limit` parts.
+fn split_unescaped(raw: &str, separator: char, limit: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in raw.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator && parts.len() + 1 < limit {
+            parts.push(&raw[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&raw[start..]);
+    parts
+}
+
+impl IcalRequestStatus {
+    pub fn to_ical(&self) -> String {
+        let mut value = format!("{};{}", self.code, escape_text(&self.description));
+        if let Some(extra) = &self.extra {
+            value.push(';');
+            value.push_str(&escape_text(extra));
+        }
+        value
+    }
+}
+
+impl TryFrom<Property> for IcalRequestStatus {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<Self, Self::Error>{
+        let value = property.value.ok_or(Error::TypeDecode(ICalTypes::Text))?;
+        match split_unescaped(&value, ';', 3).as_slice() {
+            [code, description, extra @ ..] if !code.is_empty() && code.split('.').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())) => {
+                Ok(IcalRequestStatus { code: code.to_string(), description: unescape_text(description), extra: extra.first().map(|extra| unescape_text(extra)) })
+            },
+            _ => Err(Error::TypeDecode(ICalTypes::Text)),
+        }
+    }
+}
+
 pub enum ICalTime {
     Utc {
         time: NaiveTime,
@@ -725,24 +1485,24 @@ pub enum ICalTime {
     },
 }
 
+impl ICalTime {
+    /// Applies the property's TZID, with the same rules as `IcalDateTime`.
+    fn with_tzid(self, property: &Property) -> Result<Self, Error> {
+        match (self, get_tzid(property)) {
+            (ICalTime::Utc { time }, None) => Ok(ICalTime::Utc { time }),
+            (ICalTime::Utc { .. }, Some(_)) => Err(Error::TypeDecode(ICalTypes::Time)),
+            (ICalTime::Floating { time }, None) => Ok(ICalTime::Floating { time }),
+            (ICalTime::Floating { time }, Some(tzid)) => Ok(ICalTime::Local { time, tzid }),
+            (ICalTime::Local { .. }, _) => Err(Error::TypeDecode(ICalTypes::Time)),
+        }
+    }
+}
+
 impl TryFrom<Property> for ICalTime {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match &property.value {
-            Some(value) => {
-                let tzid = get_tzid(&property);
-                match ical_type_parser::time(&value) {
-                    Ok(time) => {
-                        match (time, tzid) {
-                            (ICalTime::Utc { time }, None) => Ok(ICalTime::Utc { time }),
-                            (ICalTime::Floating { time }, None) => Ok(ICalTime::Floating { time }),
-                            (ICalTime::Floating { time }, Some(tzid)) => Ok(ICalTime::Local { time, tzid }),
-                            _ => Err(Error::TypeDecode(ICalTypes::Time))
-                        }
-                    },
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::Time))
-                }
-            },
+            Some(value) => ical_type_parser::time(value).map_err(|_| Error::TypeDecode(ICalTypes::Time))?.with_tzid(&property),
             None => Err(Error::TypeDecode(ICalTypes::Time))
         }
     }
@@ -767,21 +1527,516 @@ impl TryFrom<Property> for IcalURI {
     }
 }
 
+/// An ATTACH value, either a link or inline content. FMTTYPE is the media type of the attachment.
+pub enum IcalAttach {
+    Uri {
+        uri: Uri,
+        fmt_type: Option<String>,
+    },
+    Binary {
+        binary: ICalBinary,
+        fmt_type: Option<String>,
+    },
+}
+
+impl IcalAttach {
+    pub fn fmt_type(&self) -> Option<&str> {
+        match self {
+            IcalAttach::Uri { fmt_type, .. } | IcalAttach::Binary { fmt_type, .. } => fmt_type.as_deref(),
+        }
+    }
+}
+
+impl IcalAttach {
+    pub fn to_property(&self) -> Property {
+        let fmt_type = self.fmt_type().map(|fmt_type| ("FMTTYPE", fmt_type));
+        match self {
+            IcalAttach::Uri { uri, .. } => new_property("ATTACH", &fmt_type.into_iter().collect::<Vec<_>>(), uri.to_string()),
+            IcalAttach::Binary { binary, .. } => {
+                let params = fmt_type.into_iter().chain([("ENCODING", "BASE64"), ("VALUE", "BINARY")]).collect::<Vec<_>>();
+                new_property("ATTACH", &params, binary.to_ical())
+            },
+        }
+    }
+}
+
+impl TryFrom<Property> for IcalAttach {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<Self, Self::Error>{
+        let fmt_type = get_param(&property, "FMTTYPE").cloned();
+        match get_param(&property, "VALUE").map(|value| value.as_str()) {
+            Some("BINARY") => Ok(IcalAttach::Binary { binary: ICalBinary::try_from(property)?, fmt_type }),
+            Some("URI") | None => Ok(IcalAttach::Uri { uri: IcalURI::try_from(property)?.value, fmt_type }),
+            Some(_) => Err(Error::TypeDecode(ICalTypes::URI)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct IcalUTCOffset {
     pub offset: Duration,
 }
 
+impl IcalUTCOffset {
+    /// Writes `+HHMM`, or `+HHMMSS` when there are seconds.
+    pub fn to_ical(&self) -> String {
+        let sign = if self.offset < Duration::zero() { '-' } else { '+' };
+        let seconds = self.offset.num_seconds().abs();
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if seconds == 0 {
+            format!("{sign}{hours:02}{minutes:02}")
+        } else {
+            format!("{sign}{hours:02}{minutes:02}{seconds:02}")
+        }
+    }
+}
+
+impl FromStr for IcalUTCOffset {
+    type Err = Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        ical_type_parser::utc_offset(value).map_err(|_| Error::TypeDecode(ICalTypes::UTCOffset))
+    }
+}
+
 impl TryFrom<Property> for IcalUTCOffset {
     type Error = Error;
     fn try_from(property: Property) -> Result<Self, Self::Error>{
         match property.value {
-            Some(value) => {
-                match ical_type_parser::utc_offset(&value) {
-                    Ok(offset) => Ok(offset),
-                    Err(_) => Err(Error::TypeDecode(ICalTypes::UTCOffset))
-                }
-            },
+            Some(value) => value.parse(),
             None => Err(Error::TypeDecode(ICalTypes::UTCOffset))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strategies for the round-trip property tests.
+    mod strategies {
+        use proptest::{collection::vec, option, prelude::*, sample::select};
+
+        use super::*;
+
+        pub fn date() -> impl Strategy<Value = NaiveDate> {
+            (0..=9999i32, 1..=365u32).prop_map(|(year, day)| NaiveDate::from_yo_opt(year, day).unwrap())
+        }
+
+        pub fn date_time() -> impl Strategy<Value = NaiveDateTime> {
+            (date(), 0..24u32, 0..60u32, 0..60u32).prop_map(|(date, hour, minute, second)| date.and_hms_opt(hour, minute, second).unwrap())
+        }
+
+        pub fn ical_date_time() -> impl Strategy<Value = IcalDateTime> {
+            prop_oneof![
+                date_time().prop_map(|date_time| IcalDateTime::Utc { date_time: date_time.and_utc() }),
+                date_time().prop_map(|date_time| IcalDateTime::Floating { date_time }),
+            ]
+        }
+
+        pub fn duration() -> impl Strategy<Value = ICalDuration> {
+            prop_oneof![
+                (-10i64..=10).prop_map(|weeks| weeks * 7 * 86400),
+                (-100i64..=100).prop_map(|days| days * 86400),
+                -10_000_000i64..=10_000_000,
+            ].prop_map(|seconds| ICalDuration { duration: Duration::seconds(seconds) })
+        }
+
+        pub fn period() -> impl Strategy<Value = IcalPeriod> {
+            prop_oneof![
+                (ical_date_time(), ical_date_time()).prop_map(|(start, end)| IcalPeriod::StartEnd { start, end }),
+                (ical_date_time(), duration()).prop_map(|(start, duration)| IcalPeriod::StartDuration { start, duration }),
+            ]
+        }
+
+        pub fn utc_offset() -> impl Strategy<Value = IcalUTCOffset> {
+            (-86399i64..=86399).prop_map(|seconds| IcalUTCOffset { offset: Duration::seconds(seconds) })
+        }
+
+        /// Nonzero values in `-high..=high`.
+        fn signed(high: i64) -> impl Strategy<Value = i64> {
+            prop_oneof![1..=high, -high..=-1]
+        }
+
+        fn list<T: fmt::Debug>(element: impl Strategy<Value = T>) -> impl Strategy<Value = Option<Vec<T>>> {
+            option::of(vec(element, 1..=4))
+        }
+
+        fn week_day() -> impl Strategy<Value = ICalRecurDayOfWeek> {
+            select(vec![ICalRecurDayOfWeek::Sunday, ICalRecurDayOfWeek::Monday, ICalRecurDayOfWeek::Tuesday, ICalRecurDayOfWeek::Wednesday, ICalRecurDayOfWeek::Thursday, ICalRecurDayOfWeek::Friday, ICalRecurDayOfWeek::Saturday])
+        }
+
+        pub fn recur() -> impl Strategy<Value = IcalRecur> {
+            let frequency = select(vec![ICalRecurFrequency::Secondly, ICalRecurFrequency::Minutely, ICalRecurFrequency::Hourly, ICalRecurFrequency::Daily, ICalRecurFrequency::Weekly, ICalRecurFrequency::Monthly, ICalRecurFrequency::Yearly]);
+            let limit = option::of(prop_oneof![
+                (1..=MAX_RECUR_COUNT).prop_map(IcalRecurLimit::Count),
+                date().prop_map(|date| IcalRecurLimit::Until(IcalRecurUntil::Date(date))),
+                date_time().prop_map(|date_time| IcalRecurLimit::Until(IcalRecurUntil::DateTime(date_time.and_utc()))),
+            ]);
+            let by_day = list((week_day(), option::of(signed(53))).prop_map(|(day, nth)| IcalRecurWeekDay { day, nth_of_month: nth.map(|nth| nth as i8) }));
+            let times = (list(0..=60u8), list(0..=59u8), list(0..=23u8), by_day);
+            let days = (list(signed(31).prop_map(|day| day as i8)), list(signed(366).prop_map(|day| day as i16)), list(signed(53).prop_map(|week| week as i8)), list(1..=12u8), list(signed(366).prop_map(|position| position as i16)));
+
+            ((frequency, limit, option::of(1..=1000u64), option::of(week_day())), times, days).prop_map(
+                |((frequency, limit, interval, wkst), (by_second, by_minute, by_hour, by_day), (by_month_day, by_year_day, by_week_no, by_month, by_set_pos))| {
+                    let mut recur = IcalRecur { frequency, limit, interval, by_second, by_minute, by_hour, by_day, by_month_day, by_year_day, by_week_no, by_month, by_set_pos, wkst };
+                    recur.normalize();
+                    recur
+                },
+            )
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn dates_round_trip(date in strategies::date()) {
+            let date = IcalDate { date };
+            proptest::prop_assert_eq!(date.to_ical().parse::<IcalDate>(), Ok(date));
+        }
+
+        #[test]
+        fn date_times_round_trip(date_time in strategies::ical_date_time()) {
+            proptest::prop_assert_eq!(date_time.to_ical().parse::<IcalDateTime>(), Ok(date_time));
+        }
+
+        #[test]
+        fn durations_round_trip(duration in strategies::duration()) {
+            proptest::prop_assert_eq!(duration.to_ical().parse::<ICalDuration>(), Ok(duration));
+        }
+
+        #[test]
+        fn periods_round_trip(period in strategies::period()) {
+            proptest::prop_assert_eq!(period.to_ical().parse::<IcalPeriod>(), Ok(period));
+        }
+
+        #[test]
+        fn utc_offsets_round_trip(offset in strategies::utc_offset()) {
+            proptest::prop_assert_eq!(offset.to_ical().parse::<IcalUTCOffset>(), Ok(offset));
+        }
+
+        #[test]
+        fn recurrence_rules_round_trip(recur in strategies::recur()) {
+            proptest::prop_assert_eq!(recur.to_ical().parse::<IcalRecur>(), Ok(recur));
+        }
+
+        #[test]
+        fn iso8601_durations_round_trip(duration in strategies::duration()) {
+            proptest::prop_assert_eq!(ICalDuration::from_iso8601(&duration.to_iso8601()), Ok(duration));
+        }
+    }
+
+    fn property(value: &str) -> Property {
+        Property { name: "X-TEST".to_string(), params: None, value: Some(value.to_string()) }
+    }
+
+    #[test]
+    fn looks_up_properties_by_name() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nGEO:37.386013;-122.082932\r\nCOMMENT:First\r\nCOMMENT:Second\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = ical::IcalParser::new(input.as_bytes()).next().unwrap().unwrap();
+        let event = &calendar.events[0];
+
+        assert_eq!(super::property(event, "geo").and_then(|prop| prop.value.as_deref()), Some("37.386013;-122.082932"));
+        assert_eq!(properties(event, "COMMENT").filter_map(|prop| prop.value.as_deref()).collect::<Vec<_>>(), vec!["First", "Second"]);
+        assert!(super::property(&calendar, "GEO").is_none());
+    }
+
+    #[test]
+    fn recur_writes_back_in_rfc_order() {
+        let recur = "FREQ=YEARLY;WKST=SU;BYMONTH=3;BYDAY=2MO,-1SU;UNTIL=20301231".parse::<IcalRecur>().unwrap();
+        assert_eq!(recur.to_ical(), "FREQ=YEARLY;UNTIL=20301231;BYDAY=-1SU,2MO;BYMONTH=3;WKST=SU");
+        assert_eq!(recur.to_ical().parse::<IcalRecur>().unwrap(), recur);
+    }
+
+    #[test]
+    fn fast_date_paths_match_the_grammar() {
+        for value in ["20240229", "20230229", "2024022", "202402290", "2024-02-", "0000010１", "99991231"] {
+            assert_eq!(value.parse::<IcalDate>().map(|date| date.date), ical_type_parser::date(value).map_err(|_| Error::TypeDecode(ICalTypes::Date)), "{value}");
+        }
+        for value in ["20240229T235959", "20240229T235959Z", "20240229T240000", "20240229T235960Z", "20240229X235959", "20240229T2359590", "20240229T23595９", "20240229t235959"] {
+            assert_eq!(value.parse::<IcalDateTime>(), ical_type_parser::date_time(value).map_err(|_| Error::TypeDecode(ICalTypes::DateTime)), "{value}");
+        }
+    }
+
+    #[test]
+    fn boolean_is_case_insensitive() {
+        for (value, expected) in [("TRUE", true), ("True", true), ("true", true), ("FALSE", false), ("false", false), ("fAlSe", false)] {
+            assert_eq!(ICalBoolean::try_from(property(value)).unwrap().value, expected);
+        }
+        assert!(ICalBoolean::try_from(property("yes")).is_err());
+    }
+
+    #[test]
+    fn recur_until_keeps_the_time() {
+        let until = |value: &str| IcalRecur::try_from(property(value)).ok().and_then(|recur| recur.limit);
+        let date_time = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(9, 0, 0).unwrap().and_utc();
+        assert!(matches!(until("FREQ=DAILY;UNTIL=20240105T090000Z"), Some(IcalRecurLimit::Until(IcalRecurUntil::DateTime(until))) if until == date_time));
+        assert!(matches!(until("FREQ=DAILY;UNTIL=20240105"), Some(IcalRecurLimit::Until(IcalRecurUntil::Date(date))) if date == date_time.date_naive()));
+        assert!(IcalRecur::try_from(property("FREQ=DAILY;UNTIL=20240105T090000")).is_err());
+    }
+
+    #[test]
+    fn value_types_parse_from_str() {
+        assert_eq!("20240229".parse::<IcalDate>().unwrap().date, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert!(matches!("20240229T120000Z".parse(), Ok(IcalDateTime::Utc { .. })));
+        assert!(matches!("20240229T120000".parse(), Ok(IcalDateTime::Floating { .. })));
+        assert_eq!("PT90M".parse::<ICalDuration>().unwrap().duration, Duration::minutes(90));
+        assert!(matches!("20240229T120000Z/PT1H".parse(), Ok(IcalPeriod::StartDuration { .. })));
+        assert_eq!("FREQ=DAILY;COUNT=3".parse::<IcalRecur>().unwrap().frequency, ICalRecurFrequency::Daily);
+        assert_eq!("-0500".parse::<IcalUTCOffset>().unwrap().offset, -Duration::hours(5));
+        assert_eq!("2024-02-29".parse::<IcalDate>().err(), Some(Error::TypeDecode(ICalTypes::Date)));
+    }
+
+    #[test]
+    fn utc_offset_seconds_take_the_sign() {
+        let offset = |value: &str| value.parse::<IcalUTCOffset>().unwrap().offset;
+        assert_eq!(offset("-003045"), -(Duration::minutes(30) + Duration::seconds(45)));
+        assert_eq!(offset("+003045"), Duration::minutes(30) + Duration::seconds(45));
+        assert_eq!(offset("-000130"), -Duration::seconds(90));
+        assert_eq!(offset("+051736"), Duration::hours(5) + Duration::minutes(17) + Duration::seconds(36));
+        assert!("0530".parse::<IcalUTCOffset>().is_err());
+        assert!("+05301".parse::<IcalUTCOffset>().is_err());
+    }
+
+    #[test]
+    fn parses_date_time_lists() {
+        let mut zoned = property("20240101T090000,20240102T090000");
+        zoned.params = Some(vec![("TZID".to_string(), vec!["Europe/London".to_string()])]);
+        let date_times = IcalDateTime::list_from(&zoned).unwrap();
+        assert_eq!(date_times.len(), 2);
+        assert!(date_times.iter().all(|date_time| matches!(date_time, IcalDateTime::TimeZone { tzid, .. } if tzid == "Europe/London")));
+
+        assert!(ical_type_parser::date_time_list("20240101T090000Z,").is_err());
+        assert!(ical_type_parser::date_time_list("20240101T090000Z,20240101").is_err());
+        assert!(IcalDateTime::list_from(&property("20240101T090000Z,")).is_err());
+        assert!(IcalDateTime::list_from(&property("20240101T090000Z,20240101")).is_err());
+    }
+
+    #[test]
+    fn duration_arithmetic_keeps_the_variant() {
+        let at = |value: &str| NaiveDateTime::from_str(value).unwrap();
+        let ninety_minutes = ICalDuration { duration: Duration::minutes(90) };
+
+        let utc = IcalDateTime::Utc { date_time: at("2024-01-01T23:00:00").and_utc() };
+        assert_eq!(utc.add_duration(&ninety_minutes), IcalDateTime::Utc { date_time: at("2024-01-02T00:30:00").and_utc() });
+        let floating = IcalDateTime::Floating { date_time: at("2024-01-01T09:00:00") };
+        assert_eq!(floating.sub_duration(&ninety_minutes), IcalDateTime::Floating { date_time: at("2024-01-01T07:30:00") });
+        let zoned = IcalDateTime::TimeZone { date_time: at("2024-03-10T01:30:00"), tzid: "America/New_York".to_string() };
+        assert_eq!(zoned.add_duration(&ninety_minutes), IcalDateTime::TimeZone { date_time: at("2024-03-10T03:00:00"), tzid: "America/New_York".to_string() });
+
+        assert_eq!(floating.checked_add_duration(&ICalDuration { duration: Duration::max_value() }), None);
+    }
+
+    #[test]
+    fn periods_resolve_to_utc() {
+        let timezones = crate::timezone::tests::test_timezones();
+        let period = |value: &str, tzid: &str| {
+            let mut property = property(value);
+            property.params = Some(vec![("TZID".to_string(), vec![tzid.to_string()])]);
+            IcalPeriod::try_from(property).unwrap()
+        };
+        let utc = |value: &str| value.parse::<NaiveDateTime>().unwrap().and_utc();
+
+        let expected = Ok((utc("2024-01-01T08:00:00"), utc("2024-01-01T09:30:00")));
+        assert_eq!(period("20240101T090000/20240101T103000", "Test").to_utc_range(&timezones), expected);
+        assert_eq!(period("20240101T090000/PT1H30M", "Test").to_utc_range(&timezones), expected);
+        assert_eq!("20240101T080000Z/PT1H30M".parse::<IcalPeriod>().unwrap().to_utc_range(&timezones), expected);
+        assert_eq!(period("20240101T090000/PT1H", "Nowhere/Unknown").to_utc_range(&timezones), Err(Error::InvalidTimezone));
+    }
+
+    #[test]
+    fn utc_offsets_write_seconds_only_when_needed() {
+        let offset = |value: &str| value.parse::<IcalUTCOffset>().unwrap().to_ical();
+        assert_eq!(offset("+0530"), "+0530");
+        assert_eq!(offset("+053000"), "+0530");
+        assert_eq!(offset("-080000"), "-0800");
+        assert_eq!(offset("-080030"), "-080030");
+        assert_eq!(offset("+0000"), "+0000");
+    }
+
+    #[test]
+    fn parses_request_status() {
+        let status = |value: &str| IcalRequestStatus::try_from(property(value));
+        assert_eq!(status("2.0;Success"), Ok(IcalRequestStatus { code: "2.0".to_string(), description: "Success".to_string(), extra: None }));
+        assert_eq!(status("3.1;Invalid property value;DTSTART:96-Apr-01"), Ok(IcalRequestStatus { code: "3.1".to_string(), description: "Invalid property value".to_string(), extra: Some("DTSTART:96-Apr-01".to_string()) }));
+        assert_eq!(status("2.8; Success\\, repeating event ignored. Scheduled as a single event.;RRULE:FREQ=WEEKLY\\;INTERVAL=2"), Ok(IcalRequestStatus {
+            code: "2.8".to_string(),
+            description: " Success, repeating event ignored. Scheduled as a single event.".to_string(),
+            extra: Some("RRULE:FREQ=WEEKLY;INTERVAL=2".to_string()),
+        }));
+        assert_eq!(status("Success"), Err(Error::TypeDecode(ICalTypes::Text)));
+        assert_eq!(status("2..0;Success"), Err(Error::TypeDecode(ICalTypes::Text)));
+    }
+
+    #[test]
+    fn converts_iso8601_durations() {
+        let iso = |value: &str| ICalDuration::from_iso8601(value).map(|duration| duration.duration);
+        assert_eq!(iso("P1DT2H"), Ok(Duration::hours(26)));
+        assert_eq!(iso("PT1H5S"), Ok(Duration::seconds(3605)));
+        assert_eq!(iso("PT1.5S"), Ok(Duration::milliseconds(1500)));
+        assert_eq!(iso("-P2W"), Ok(Duration::weeks(-2)));
+        assert_eq!(iso("P1Y"), Err(Error::TypeDecode(ICalTypes::Duration)));
+        assert_eq!(iso("P1M"), Err(Error::TypeDecode(ICalTypes::Duration)));
+        assert_eq!(iso("P1Y2M3D"), Err(Error::TypeDecode(ICalTypes::Duration)));
+        assert_eq!(iso("PT"), Err(Error::TypeDecode(ICalTypes::Duration)));
+        assert_eq!(iso("P"), Err(Error::TypeDecode(ICalTypes::Duration)));
+
+        let written = |duration: Duration| ICalDuration { duration }.to_iso8601();
+        assert_eq!(written(Duration::hours(26)), "P1DT2H");
+        assert_eq!(written(Duration::seconds(3605)), "PT1H5S");
+        assert_eq!(written(Duration::weeks(1)), "P7D");
+        assert_eq!(written(Duration::milliseconds(-1500)), "-PT1.5S");
+        assert_eq!(written(Duration::zero()), "PT0S");
+    }
+
+    #[test]
+    fn utc_values_reject_tzid() {
+        let zoned = |value: &str| {
+            let mut property = property(value);
+            property.params = Some(vec![("TZID".to_string(), vec!["Europe/London".to_string()])]);
+            property
+        };
+
+        assert_eq!(IcalDateTime::try_from(zoned("20240101T090000Z")), Err(Error::TypeDecode(ICalTypes::DateTime)));
+        assert_eq!(IcalDateTime::try_from_lenient(zoned("20240101T090000+0000")), Err(Error::TypeDecode(ICalTypes::DateTime)));
+        assert_eq!(IcalDateTime::list_from(&zoned("20240101T090000Z,20240102T090000Z")), Err(Error::TypeDecode(ICalTypes::DateTime)));
+        assert!(matches!(ICalTime::try_from(zoned("090000Z")), Err(Error::TypeDecode(ICalTypes::Time))));
+
+        assert!(matches!(IcalDateTime::try_from(zoned("20240101T090000")), Ok(IcalDateTime::TimeZone { .. })));
+        assert!(matches!(ICalTime::try_from(zoned("090000")), Ok(ICalTime::Local { .. })));
+        assert!(matches!(ICalTime::try_from(property("090000Z")), Ok(ICalTime::Utc { .. })));
+    }
+
+    #[test]
+    fn numeric_offsets_parse_leniently() {
+        let utc = |value: &str| NaiveDateTime::from_str(value).unwrap().and_utc();
+        assert!(IcalDateTime::try_from(property("20240101T090000+0000")).is_err());
+        assert_eq!(IcalDateTime::try_from_lenient(property("20240101T090000+0000")), Ok(IcalDateTime::Utc { date_time: utc("2024-01-01T09:00:00") }));
+        assert_eq!(IcalDateTime::try_from_lenient(property("20240101T090000-05:00")), Ok(IcalDateTime::Utc { date_time: utc("2024-01-01T14:00:00") }));
+        assert_eq!(IcalDateTime::try_from_lenient(property("20240101T090000+0130")), Ok(IcalDateTime::Utc { date_time: utc("2024-01-01T07:30:00") }));
+        assert!(matches!(IcalDateTime::try_from_lenient(property("20240101T090000")), Ok(IcalDateTime::Floating { .. })));
+    }
+
+    #[test]
+    fn text_round_trips_raw_value() {
+        let text = IcalText::try_from(property("Lunch\\, then a\\Nwalk\\; maybe")).unwrap();
+        assert_eq!(text.value, "Lunch\\, then a\\Nwalk\\; maybe");
+        assert_eq!(text.unescaped(), "Lunch, then a\nwalk; maybe");
+        assert_eq!(text.to_ical(), "Lunch\\, then a\\Nwalk\\; maybe");
+
+        assert_eq!(IcalText::new("Lunch, then a\nwalk").to_ical(), "Lunch\\, then a\\nwalk");
+        assert_eq!(IcalText::new("C:\\dir").to_ical(), "C:\\\\dir");
+    }
+
+    #[test]
+    fn scalars_display_their_values() {
+        let text = IcalText::try_from(property("Lunch\\, maybe")).unwrap();
+        assert_eq!(text.to_string(), "Lunch, maybe");
+        assert!(text.starts_with("Lunch"));
+        assert_eq!(ICalBoolean { value: true }.to_string(), "TRUE");
+        assert_eq!(IcalInteger { value: -3 }.to_string(), "-3");
+        assert_eq!(format!("{:.1}", IcalFloat { value: 1.25 }), "1.2");
+    }
+
+    #[test]
+    fn text_ref_borrows_unless_escaped() {
+        let plain = property("Team lunch");
+        let text = IcalTextRef::try_from(&plain).unwrap();
+        assert!(matches!(text.unescaped(), Cow::Borrowed("Team lunch")));
+
+        let escaped = property("Lunch\\, then a\\Nwalk");
+        let text = IcalTextRef::try_from(&escaped).unwrap();
+        assert!(matches!(text.unescaped(), Cow::Owned(value) if value == "Lunch, then a\nwalk"));
+        assert_eq!(IcalText::from(text), IcalText::try_from(escaped.clone()).unwrap());
+    }
+
+    #[test]
+    fn attach_dispatches_on_value() {
+        let mut binary = property("aGVsbG8=");
+        binary.params = Some(vec![
+            ("FMTTYPE".to_string(), vec!["text/plain".to_string()]),
+            ("ENCODING".to_string(), vec!["BASE64".to_string()]),
+            ("VALUE".to_string(), vec!["BINARY".to_string()]),
+        ]);
+        match IcalAttach::try_from(binary).unwrap() {
+            IcalAttach::Binary { binary, fmt_type } => {
+                assert_eq!(binary.data, b"hello");
+                assert_eq!(fmt_type.as_deref(), Some("text/plain"));
+            },
+            IcalAttach::Uri { .. } => panic!("expected inline binary"),
+        }
+
+        let attach = IcalAttach::try_from(property("https://example.com/agenda.pdf")).unwrap();
+        assert!(matches!(&attach, IcalAttach::Uri { uri, .. } if uri.host() == Some("example.com")));
+        assert_eq!(attach.fmt_type(), None);
+    }
+
+    #[test]
+    fn durations_order_by_length() {
+        let duration = |value: &str| value.parse::<ICalDuration>().unwrap();
+        assert!(duration("PT1H") > duration("PT30M"));
+        assert!(duration("-PT1H") < duration("PT0S"));
+        assert_eq!(duration("P1W"), duration("P7D"));
+
+        let mut durations = vec![duration("P1D"), duration("PT15M"), duration("PT1H")];
+        durations.sort();
+        assert_eq!(durations, vec![duration("PT15M"), duration("PT1H"), duration("P1D")]);
+    }
+
+    #[test]
+    fn durations_without_components_are_rejected() {
+        for value in ["P", "PT", "-P", "+PT", "P1DT"] {
+            assert_eq!(ICalDuration::try_from(property(value)), Err(Error::TypeDecode(ICalTypes::Duration)), "{value}");
+            assert_eq!(ICalDuration::try_from_lenient(property(value)), Err(Error::TypeDecode(ICalTypes::Duration)), "{value}");
+        }
+    }
+
+    #[test]
+    fn overflowing_durations_are_rejected() {
+        for value in ["P99999999999999999999D", "P99999999999999W", "PT9999999999999999H", "P1DT9223372036854775807S"] {
+            assert_eq!(ICalDuration::try_from(property(value)), Err(Error::TypeDecode(ICalTypes::Duration)), "{value}");
+        }
+        assert_eq!(ICalDuration::try_from_lenient(property("P99999999999999WT1S")), Err(Error::TypeDecode(ICalTypes::Duration)));
+    }
+
+    #[test]
+    fn weeks_with_days_only_parse_leniently() {
+        assert!(ICalDuration::try_from(property("P1W2D")).is_err());
+        assert_eq!(ICalDuration::try_from_lenient(property("P1W2D")).unwrap().duration, Duration::days(9));
+        assert_eq!(ICalDuration::try_from_lenient(property("-P1WT1H")).unwrap().duration, -(Duration::weeks(1) + Duration::hours(1)));
+        assert_eq!(ICalDuration::try_from_lenient(property("PT15M")).unwrap().duration, Duration::minutes(15));
+    }
+
+    #[test]
+    fn recur_numbers_parse_without_panicking() {
+        let recur = IcalRecur::try_from(property("FREQ=YEARLY;BYSECOND=99;BYMONTHDAY=-99;BYYEARDAY=-999;BYDAY=-99MO")).unwrap();
+        assert_eq!(recur.by_second, Some(vec![99]));
+        assert_eq!(recur.by_month_day, Some(vec![-99]));
+        assert_eq!(recur.by_year_day, Some(vec![-999]));
+        assert!(recur.validate().is_err());
+
+        assert!(IcalRecur::try_from(property("FREQ=DAILY;INTERVAL=99999999999999999999")).is_err());
+    }
+
+    #[test]
+    fn count_is_bounded() {
+        assert_eq!("FREQ=DAILY;COUNT=1000000".parse::<IcalRecur>().unwrap().limit, Some(IcalRecurLimit::Count(MAX_RECUR_COUNT)));
+        assert!("FREQ=DAILY;COUNT=1000001".parse::<IcalRecur>().is_err());
+        assert!("FREQ=DAILY;COUNT=99999999999999999999999".parse::<IcalRecur>().is_err());
+    }
+
+    #[test]
+    fn equivalent_rules_compare_equal() {
+        let a = "FREQ=WEEKLY;BYDAY=MO,WE;BYHOUR=17,9,9".parse::<IcalRecur>().unwrap();
+        let b = "FREQ=WEEKLY;BYHOUR=9,17;BYDAY=WE,MO".parse::<IcalRecur>().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.by_hour, Some(vec![9, 17]));
+    }
+
+    #[test]
+    fn recur_keywords_are_case_insensitive() {
+        let recur = IcalRecur::try_from(property("freq=weekly;Interval=2;byday=mo,We")).unwrap();
+        assert_eq!(recur.frequency, ICalRecurFrequency::Weekly);
+        assert_eq!(recur.interval, Some(2));
+        let days = recur.by_day.unwrap().iter().map(|day| day.day).collect::<Vec<_>>();
+        assert_eq!(days, vec![ICalRecurDayOfWeek::Monday, ICalRecurDayOfWeek::Wednesday]);
+    }
+}